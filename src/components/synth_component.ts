@@ -1,4 +1,11 @@
-import { EchoParams, Effects, FilterParams, OscKey, SynthApi } from "../sound/synth_api_service";
+import {
+  EchoParams,
+  Effects,
+  FilterParams,
+  FilterType,
+  OscKey,
+  SynthApi,
+} from "../sound/synth_api_service";
 
 const keys = ["q", "z", "s", "e", "d", "f", "t", "g", "y", "h", "u", "j"];
 
@@ -317,7 +324,21 @@ export class SynthComponent {
     const frequency = this.create_slider(filter, "Frequency (hz)", 50, 5_000, 10, 800);
     const q = this.create_slider(filter, "q", 0.2, 100, 0.1, 0.7);
 
-    const types = ["lowpass", "highpass", "bell"];
+    const types = [
+      "lowpass",
+      "highpass",
+      "bell",
+      "bandpass",
+      "notch",
+      "allpass",
+      "low shelf",
+      "high shelf",
+      "lowpass 24 dB",
+      "highpass 24 dB",
+      "lowpass 48 dB",
+      "highpass 48 dB",
+    ];
+    const types_with_gain = [FilterType.BELL, FilterType.LOW_SHELF, FilterType.HIGH_SHELF];
     const select_type = document.createElement("select");
     select_type.name = "filter_type";
 
@@ -342,9 +363,11 @@ export class SynthComponent {
     let gain_handler: ((e: Event) => void) | null = null;
 
     select_type.addEventListener("change", () => {
-      this.api.edit_fx(id, FilterParams.TYPE, parseInt(select_type.value));
-      if (parseInt(select_type.value) === 2) {
-        gain = this.create_slider(filter, "gain (db)", 0, 20, 0.1, 5);
+      const filter_type = parseInt(select_type.value);
+      this.api.edit_fx(id, FilterParams.TYPE, filter_type);
+      if (types_with_gain.includes(filter_type)) {
+        if (gain) return;
+        gain = this.create_slider(filter, "gain (db)", -20, 20, 0.1, 5);
         filter.appendChild(gain);
         gain_handler = () => {
           if (!gain) return;
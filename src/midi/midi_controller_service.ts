@@ -1,10 +1,24 @@
-import { SynthApi } from "../sound/synth_api_service";
+import { MidiEventType, SynthApi } from "../sound/synth_api_service";
+
+// Période de relève de la file sortante du moteur (horloge MIDI émise)
+const MIDI_OUT_POLL_MS = 5;
+
+// Messages système transmis au moteur, les autres (sysex, active sensing...) sont ignorés
+const REALTIME_TYPES: number[] = [
+  MidiEventType.SONG_POSITION,
+  MidiEventType.CLOCK,
+  MidiEventType.TRANSPORT_START,
+  MidiEventType.TRANSPORT_CONTINUE,
+  MidiEventType.TRANSPORT_STOP,
+];
 
 export class MidiController {
   private midiAccess: MIDIAccess | null = null;
+  private output: MIDIOutput | null = null;
 
   constructor() {
     this.requestMidiAccess();
+    setInterval(() => this.flushMidiOut(), MIDI_OUT_POLL_MS);
   }
 
   private async requestMidiAccess() {
@@ -25,20 +39,47 @@ export class MidiController {
     inputs.forEach((input) => {
       input.onmidimessage = (ev) => this.handleMidiMessage(ev);
     });
+
+    // Première sortie par défaut, modifiable avec selectOutput
+    this.output = midiAccess.outputs.values().next().value ?? null;
+  }
+
+  // Sortie qui reçoit l'horloge émise par le moteur, null pour ne rien envoyer
+  selectOutput(id: string | null) {
+    this.output = (id !== null && this.midiAccess?.outputs.get(id)) || null;
+  }
+
+  private flushMidiOut() {
+    SynthApi.drainMidiOut((message) => this.output?.send(message));
   }
 
   private handleMidiMessage(ev: MIDIMessageEvent) {
     if (!ev.data) return;
-    const status = ev.data[0];
-    const note = ev.data[1];
-    const velocity = ev.data[2];
 
-    const isNoteOn = status === 153 && velocity > 0;
+    // Messages système : pas de canal, transmis tels quels
+    if (ev.data[0] >= 0xf0) {
+      if (REALTIME_TYPES.includes(ev.data[0])) {
+        SynthApi.sendMidiRealtime(ev.data[0], ev.data[1] ?? 0, ev.data[2] ?? 0);
+      }
+      return;
+    }
+
+    const type = ev.data[0] & 0xf0;
+    const channel = ev.data[0] & 0x0f;
+    const data_1 = ev.data[1];
+    const data_2 = ev.data[2];
 
-    if (isNoteOn) {
-      SynthApi.playNote({ value: note });
-    } else if (status === 137 || (status === 153 && velocity === 0)) {
-      SynthApi.stopNote(note);
+    // Tous les canaux sont transmis : le moteur route vers les parties et les zones MPE
+    if (type === 0x90 && data_2 > 0) {
+      SynthApi.playNote({ value: data_1, velocity: data_2, channel });
+    } else if (type === 0x80 || type === 0x90) {
+      SynthApi.stopNote(data_1, channel);
+    } else if (type === 0xb0) {
+      SynthApi.sendControlChange(data_1, data_2, channel);
+    } else if (type === 0xe0) {
+      SynthApi.sendPitchBend((data_2 << 7) | data_1, channel);
+    } else if (type === 0xd0) {
+      SynthApi.sendChannelPressure(data_1, channel);
     }
   }
 }
@@ -38,7 +38,12 @@ export class AudioEngineOrchestrator {
     osc_queue_buffer: SharedArrayBuffer,
     fx_queue_buffer: SharedArrayBuffer,
     sample_event_buffer: SharedArrayBuffer,
-    sample_buffer: SharedArrayBuffer
+    sample_buffer: SharedArrayBuffer,
+    control_queue_buffer: SharedArrayBuffer,
+    file_event_buffer: SharedArrayBuffer,
+    file_buffer: SharedArrayBuffer,
+    report_buffer: SharedArrayBuffer,
+    midi_out_buffer: SharedArrayBuffer
   ): Promise<Worker | null> {
     try {
       if (this.audioCtx) return null;
@@ -63,6 +68,11 @@ export class AudioEngineOrchestrator {
             fx_queue_buffer: fx_queue_buffer,
             sample_event_buffer: sample_event_buffer,
             sample_buffer: sample_buffer,
+            control_queue_buffer: control_queue_buffer,
+            file_event_buffer: file_event_buffer,
+            file_buffer: file_buffer,
+            report_buffer: report_buffer,
+            midi_out_buffer: midi_out_buffer,
           });
         }
       };
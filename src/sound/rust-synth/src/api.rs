@@ -3,19 +3,24 @@ use wasm_bindgen::prelude::wasm_bindgen;
 use web_sys::console;
 
 use crate::{
-    global::{AUDIO_PROCESSOR, SHARED_BUFFERS},
+    global::{AUDIO_PROCESSOR, SHARED_BUFFERS, TRANSPORT},
     shared_memory::{
         ring_buffer_manager::RingBufferManager,
-        shared_buffers::{AudioBuffers, FxBuffers, MidiBuffers, SamplerBuffers, SharedBuffers},
+        shared_buffers::{
+            AudioBuffers, ControlBuffers, FileBuffers, FxBuffers, MidiBuffers, SamplerBuffers,
+            SharedBuffers,
+        },
     },
     sound_engine::{event_handler::EventHandler, processor::AudioProcessor},
     utils::constants::{
-        FLAG_INDEX, FX_QUEUE_CAPACITY, HEADERS_SIZE_BYTES, MIDI_READ_INDEX, MIDI_WRITE_INDEX,
-        READ_INDEX, WRITE_INDEX,
+        CONTROL_EVENT_SIZE_INT, CONTROL_QUEUE_CAPACITY, FILE_DATA_SIZE, FLAG_INDEX,
+        FX_QUEUE_CAPACITY, HEADERS_SIZE_BYTES, MIDI_READ_INDEX, MIDI_WRITE_INDEX, READ_INDEX,
+        WRITE_INDEX,
     },
 };
 
 #[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
 pub fn init_audio_thread(
     shared_audio_buffer: SharedArrayBuffer,
     ring_buffer_size: u32,
@@ -24,6 +29,11 @@ pub fn init_audio_thread(
     fx_buffer: SharedArrayBuffer,
     sample_event_buffer: SharedArrayBuffer,
     sample_buffer: SharedArrayBuffer,
+    control_buffer: SharedArrayBuffer,
+    file_event_buffer: SharedArrayBuffer,
+    file_buffer: SharedArrayBuffer,
+    report_buffer: SharedArrayBuffer,
+    midi_out_buffer: SharedArrayBuffer,
 ) {
     init_shared_buffers(
         &shared_audio_buffer,
@@ -33,6 +43,11 @@ pub fn init_audio_thread(
         fx_buffer,
         sample_event_buffer,
         sample_buffer,
+        control_buffer,
+        file_event_buffer,
+        file_buffer,
+        report_buffer,
+        midi_out_buffer,
     );
     init_audio_processor();
     console::log_1(&"Buffers et processeur audio initialisés".into());
@@ -44,6 +59,7 @@ fn init_audio_processor() {
     AUDIO_PROCESSOR.with(|p| *p.borrow_mut() = Some(audio_processor));
 }
 
+#[allow(clippy::too_many_arguments)]
 fn init_shared_buffers(
     shared_audio_buffer: &SharedArrayBuffer,
     ring_buffer_size: u32,
@@ -52,6 +68,11 @@ fn init_shared_buffers(
     fx_buffer: SharedArrayBuffer,
     sample_event_buffer: SharedArrayBuffer,
     sample_buffer: SharedArrayBuffer,
+    control_buffer: SharedArrayBuffer,
+    file_event_buffer: SharedArrayBuffer,
+    file_buffer: SharedArrayBuffer,
+    report_buffer: SharedArrayBuffer,
+    midi_out_buffer: SharedArrayBuffer,
 ) {
     // -------- Audio --------
     let control_arr = Int32Array::new(&shared_audio_buffer);
@@ -70,6 +91,13 @@ fn init_shared_buffers(
     let midi_read_idx = midi_control_arr.subarray(MIDI_READ_INDEX, MIDI_READ_INDEX + 1);
     let midi_queue = Uint8Array::new(&midi_buffer).subarray(8, midi_buffer.byte_length());
 
+    // -------- MIDI sortant --------
+    let midi_out_control_arr = Int32Array::new(&midi_out_buffer);
+    let midi_out_write_idx = midi_out_control_arr.subarray(MIDI_WRITE_INDEX, MIDI_WRITE_INDEX + 1);
+    let midi_out_read_idx = midi_out_control_arr.subarray(MIDI_READ_INDEX, MIDI_READ_INDEX + 1);
+    let midi_out_queue =
+        Uint8Array::new(&midi_out_buffer).subarray(8, midi_out_buffer.byte_length());
+
     // -------- Sampler --------
     let osc_control_arr = Int32Array::new(&osc_buffer);
     let osc_write_idx = osc_control_arr.subarray(0, 1);
@@ -104,6 +132,38 @@ fn init_shared_buffers(
         fx_float_offset / 4 + FX_QUEUE_CAPACITY,
     );
 
+    // -------- Contrôle ------------------
+
+    let control_control_arr = Int32Array::new(&control_buffer);
+    let control_write_idx = control_control_arr.subarray(0, 1);
+    let control_read_idx = control_control_arr.subarray(1, 2);
+
+    // même découpage que la file FX : les entiers puis les flottants
+    let control_int_offset = 2 * 4;
+    let control_float_offset =
+        control_int_offset + CONTROL_EVENT_SIZE_INT * CONTROL_QUEUE_CAPACITY * 4;
+
+    let control_queue_int = Int32Array::new(&control_buffer).subarray(
+        control_int_offset / 4,
+        control_int_offset / 4 + CONTROL_EVENT_SIZE_INT * CONTROL_QUEUE_CAPACITY,
+    );
+
+    let control_queue_float = Float32Array::new(&control_buffer).subarray(
+        control_float_offset / 4,
+        control_float_offset / 4 + CONTROL_QUEUE_CAPACITY,
+    );
+
+    // -------- Fichiers ------------------
+
+    let file_event_view = Int32Array::new(&file_event_buffer);
+    let file_view = Uint8Array::new(&file_buffer);
+    let file_data_view = file_view.subarray(0, FILE_DATA_SIZE);
+    let file_response_view = file_view.subarray(FILE_DATA_SIZE, file_view.length());
+
+    // -------- Rapport ------------------
+
+    let report_view = Float32Array::new(&report_buffer);
+
     // -------- SharedBuffers --------
     let shared_buffers = SharedBuffers {
         audio: AudioBuffers {
@@ -117,6 +177,11 @@ fn init_shared_buffers(
             read_idx: midi_read_idx,
             queue: midi_queue,
         },
+        midi_out: MidiBuffers {
+            write_idx: midi_out_write_idx,
+            read_idx: midi_out_read_idx,
+            queue: midi_out_queue,
+        },
         osc: SamplerBuffers {
             write_idx: osc_write_idx,
             read_idx: osc_read_idx,
@@ -128,8 +193,20 @@ fn init_shared_buffers(
             queue_int: fx_queue_int,
             queue_float: fx_queue_float,
         },
+        control: ControlBuffers {
+            write_idx: control_write_idx,
+            read_idx: control_read_idx,
+            queue_int: control_queue_int,
+            queue_float: control_queue_float,
+        },
+        file: FileBuffers {
+            event: file_event_view,
+            data: file_data_view,
+            response: file_response_view,
+        },
         sample_event: sample_event_view,
         sample_buffer: sample_buffer_view,
+        report: report_view,
     };
 
     _ = SHARED_BUFFERS.with(|cell| cell.set(shared_buffers));
@@ -158,7 +235,11 @@ fn main_loop(buffers: &SharedBuffers) {
 
         AUDIO_PROCESSOR.with(|processor_cell| {
             if let Some(ref mut processor) = *processor_cell.borrow_mut() {
-                update_input_event_buffers(&mut processor.event_handler, buffers);
+                update_input_event_buffers(
+                    &mut processor.event_handler,
+                    buffers,
+                    processor.global_sample_index,
+                );
 
                 let r_idx = Atomics::load(read_idx, 0).unwrap();
                 let w_idx = Atomics::load(write_idx, 0).unwrap();
@@ -174,6 +255,14 @@ fn main_loop(buffers: &SharedBuffers) {
                     processor
                         .process_and_fill_audio_buffer(sample_count_frames, &ring_buffer_manager);
                 }
+
+                processor
+                    .event_handler
+                    .midi_clock
+                    .send_events(&buffers.midi_out);
+
+                TRANSPORT.with(|t| t.lock().unwrap().write_report(&buffers.report));
+                processor.event_handler.write_report(&buffers.report);
             }
         });
 
@@ -182,12 +271,22 @@ fn main_loop(buffers: &SharedBuffers) {
     }
 }
 
-fn update_input_event_buffers(event_handler: &mut EventHandler, buffers: &SharedBuffers) {
-    event_handler.process_midi_events(&buffers.midi);
+fn update_input_event_buffers(
+    event_handler: &mut EventHandler,
+    buffers: &SharedBuffers,
+    sample_index: u64,
+) {
+    event_handler.process_midi_events(&buffers.midi, sample_index);
 
     event_handler.process_osc_events(&buffers.osc);
 
     event_handler.process_fx_events(&buffers.fx);
 
     event_handler.process_sample_event(&buffers.sample_event);
+
+    event_handler.process_control_events(&buffers.control);
+
+    event_handler.process_file_event(&buffers.file);
+
+    event_handler.process_file_responses(&buffers.file);
 }
@@ -7,10 +7,14 @@ use crate::shared_memory::shared_buffers::SharedBuffers;
 use crate::sound_engine::dsp::mixer::Mixer;
 use crate::sound_engine::processor::AudioProcessor;
 use crate::sound_engine::synthetizer::sample_manager::SampleManager;
+use crate::sound_engine::transport::Transport;
+use crate::sound_engine::tuning::tuning_table::TuningTable;
+use crate::utils::random::Random;
 
 thread_local! {
     pub static SHARED_BUFFERS: OnceCell<SharedBuffers> = OnceCell::new();
     pub static AUDIO_PROCESSOR: RefCell<Option<AudioProcessor>> = RefCell::new(None);
+    pub static RANDOM: RefCell<Random> = const { RefCell::new(Random::new(0x1234_5678)) };
 
     pub static MIXER: Lazy<Mutex<Mixer>> = Lazy::new(|| {
         Mutex::new(Mixer::new())
@@ -18,5 +22,13 @@ thread_local! {
 
     pub static SAMPLE_MANAGER: Lazy<Mutex<SampleManager>> = Lazy::new(|| {
         Mutex::new(SampleManager::new())
+    });
+
+    pub static TUNING: Lazy<Mutex<TuningTable>> = Lazy::new(|| {
+        Mutex::new(TuningTable::new())
+    });
+
+    pub static TRANSPORT: Lazy<Mutex<Transport>> = Lazy::new(|| {
+        Mutex::new(Transport::new())
     })
 }
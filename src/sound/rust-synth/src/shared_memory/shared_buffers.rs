@@ -1,8 +1,12 @@
 use js_sys::{Atomics, Float32Array, Int32Array, Uint8Array};
 
 use crate::utils::{
-    constants::{FX_QUEUE_CAPACITY, MIDI_EVENT_SIZE, MIDI_QUEUE_CAPACITY},
-    types::NoteDTO,
+    constants::{
+        CONTROL_EVENT_SIZE_FLOAT, CONTROL_EVENT_SIZE_INT, CONTROL_QUEUE_CAPACITY,
+        FILE_RESPONSE_INDEX, FILE_RESPONSE_KIND, FILE_RESPONSE_LENGTH, FX_QUEUE_CAPACITY,
+        MIDI_EVENT_SIZE, MIDI_QUEUE_CAPACITY,
+    },
+    types::{ControlEventDto, MidiEventDTO, NoteDTO},
 };
 
 pub struct AudioBuffers {
@@ -19,7 +23,7 @@ pub struct MidiBuffers {
 }
 
 impl MidiBuffers {
-    pub fn dequeue_event(&self) -> Option<NoteDTO> {
+    pub fn dequeue_event(&self) -> Option<MidiEventDTO> {
         let read_pos = Atomics::load(&self.read_idx, 0).unwrap() as u32;
         let write_pos = Atomics::load(&self.write_idx, 0).unwrap() as u32;
 
@@ -28,22 +32,47 @@ impl MidiBuffers {
         }
 
         let event_offset = read_pos * MIDI_EVENT_SIZE;
-        let _event_type = self.queue.get_index(event_offset);
+        let event_type = self.queue.get_index(event_offset);
         let note_value = self.queue.get_index(event_offset + 1);
         let velocity = self.queue.get_index(event_offset + 2);
+        let channel = self.queue.get_index(event_offset + 3) & 0x0F;
 
         let new_read_pos = (read_pos + 1) % MIDI_QUEUE_CAPACITY;
         Atomics::store(&self.read_idx, 0, new_read_pos as i32).unwrap();
 
-        Some(NoteDTO {
-            value: note_value,
-            velocity,
+        Some(MidiEventDTO {
+            event_type,
+            note: NoteDTO {
+                value: note_value,
+                velocity,
+                channel,
+            },
         })
     }
 
+    /// Écriture côté moteur, pour la file MIDI sortante. Renvoie `false` si la file est pleine
+    pub fn enqueue_event(&self, event_type: u8, data_1: u8, data_2: u8) -> bool {
+        let write_pos = Atomics::load(&self.write_idx, 0).unwrap() as u32;
+        let read_pos = Atomics::load(&self.read_idx, 0).unwrap() as u32;
+
+        let next_write_pos = (write_pos + 1) % MIDI_QUEUE_CAPACITY;
+        if next_write_pos == read_pos {
+            return false;
+        }
+
+        let event_offset = write_pos * MIDI_EVENT_SIZE;
+        self.queue.set_index(event_offset, event_type);
+        self.queue.set_index(event_offset + 1, data_1);
+        self.queue.set_index(event_offset + 2, data_2);
+        self.queue.set_index(event_offset + 3, 0);
+
+        Atomics::store(&self.write_idx, 0, next_write_pos as i32).unwrap();
+        true
+    }
+
     pub fn process_all_events<F>(&self, mut handler: F) -> u32
     where
-        F: FnMut(&NoteDTO),
+        F: FnMut(&MidiEventDTO),
     {
         let mut events_processed = 0;
 
@@ -69,13 +98,98 @@ pub struct SamplerBuffers {
     pub queue: Uint8Array, // 8 octets par événement
 }
 
+pub struct ControlBuffers {
+    pub write_idx: Int32Array,
+    pub read_idx: Int32Array,
+    pub queue_int: Int32Array,     // target, param, index
+    pub queue_float: Float32Array, // value
+}
+
+impl ControlBuffers {
+    pub fn dequeue_event(&self) -> Option<ControlEventDto> {
+        let read_pos = Atomics::load(&self.read_idx, 0).unwrap() as u32;
+        let write_pos = Atomics::load(&self.write_idx, 0).unwrap() as u32;
+
+        if read_pos == write_pos {
+            return None;
+        }
+
+        let int_offset = read_pos * CONTROL_EVENT_SIZE_INT;
+        let float_offset = read_pos * CONTROL_EVENT_SIZE_FLOAT;
+
+        let target = self.queue_int.get_index(int_offset) as u32;
+        let param = self.queue_int.get_index(int_offset + 1) as u32;
+        let index = self.queue_int.get_index(int_offset + 2) as u32;
+
+        let value = self.queue_float.get_index(float_offset);
+
+        let new_read_pos = (read_pos + 1) % CONTROL_QUEUE_CAPACITY;
+        Atomics::store(&self.read_idx, 0, new_read_pos as i32).unwrap();
+
+        Some(ControlEventDto {
+            target,
+            param,
+            index,
+            value,
+        })
+    }
+
+    pub fn process_all_events<F>(&self, mut handler: F) -> u32
+    where
+        F: FnMut(&ControlEventDto),
+    {
+        let mut events_processed = 0;
+
+        while let Some(dto) = self.dequeue_event() {
+            events_processed += 1;
+            handler(&dto);
+        }
+
+        events_processed
+    }
+}
+
+/// Transfert de fichiers (gammes, etc.) : un en-tête d'événement et les octets bruts
+pub struct FileBuffers {
+    pub event: Int32Array, // index, kind, length, puis index, kind, length de la réponse
+    pub data: Uint8Array,
+    /// Zone distincte de `data`, qu'un nouvel envoi ne peut pas écraser
+    pub response: Uint8Array,
+}
+
+impl FileBuffers {
+    /// Renvoie un fichier produit par le moteur (export) via la zone de réponse
+    pub fn write_response(&self, kind: u32, bytes: &[u8]) -> Result<(), &'static str> {
+        if bytes.len() > self.response.length() as usize {
+            return Err("Fichier trop volumineux pour le buffer d'échange");
+        }
+
+        self.response
+            .subarray(0, bytes.len() as u32)
+            .copy_from(bytes);
+        self.event.set_index(FILE_RESPONSE_KIND, kind as i32);
+        self.event
+            .set_index(FILE_RESPONSE_LENGTH, bytes.len() as i32);
+        // L'index est publié en dernier : l'interface le surveille pour lire la réponse
+        Atomics::add(&self.event, FILE_RESPONSE_INDEX, 1).unwrap();
+
+        Ok(())
+    }
+}
+
 pub struct SharedBuffers {
     pub audio: AudioBuffers,
     pub midi: MidiBuffers,
+    /// File MIDI sortante (horloge), même format que la file entrante
+    pub midi_out: MidiBuffers,
     pub osc: SamplerBuffers,
     pub fx: FxBuffers,
+    pub control: ControlBuffers,
+    pub file: FileBuffers,
     pub sample_event: Int32Array,
     pub sample_buffer: Float32Array,
+    /// État publié vers l'interface (transport, ...)
+    pub report: Float32Array,
 }
 
 pub struct FxEventDto {
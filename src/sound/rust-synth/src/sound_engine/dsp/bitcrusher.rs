@@ -0,0 +1,104 @@
+use std::any::Any;
+
+use web_sys::console;
+
+use crate::{
+    global::RANDOM,
+    sound_engine::dsp::fx::EffectTrait,
+    utils::{constants::SAMPLE_RATE, types::Mix},
+};
+
+const MIN_BITS: f32 = 1.0;
+const MAX_BITS: f32 = 24.0;
+const MIN_RATE: f32 = 100.0;
+
+/// Effet lo-fi : échantillonneur-bloqueur à fréquence réduite puis quantification
+/// sur un nombre de bits réduit, avec dither triangulaire et gigue de l'horloge
+pub struct Bitcrusher {
+    id: usize,
+    /// Résolution en bits, les valeurs fractionnaires donnant un réglage continu
+    pub bits: f32,
+    /// Fréquence d'échantillonnage simulée, en Hz
+    pub rate: f32,
+    /// Quantité de dither, 1 pour un dither triangulaire d'un pas de quantification
+    pub dither: f32,
+    /// Variation aléatoire de la période d'échantillonnage, entre 0 et 1
+    pub jitter: f32,
+    pub mix: Mix,
+
+    phase: f32,
+    threshold: f32,
+    held: [f32; 2],
+}
+
+impl Bitcrusher {
+    pub fn new(id: usize) -> Self {
+        Self {
+            id,
+            bits: 8.0,
+            rate: 11_025.0,
+            dither: 0.0,
+            jitter: 0.0,
+            mix: Mix { dry: 0.0, wet: 1.0 },
+            phase: 1.0,
+            threshold: 1.0,
+            held: [0.0; 2],
+        }
+    }
+
+    /// Paramètres : 0 bits, 1 fréquence (Hz), 2 dither, 3 gigue, 4 dry, 5 wet
+    pub fn update(&mut self, param: u32, value: f32) {
+        match param {
+            0 => self.bits = value.clamp(MIN_BITS, MAX_BITS),
+            1 => self.rate = value.clamp(MIN_RATE, SAMPLE_RATE),
+            2 => self.dither = value.clamp(0.0, 1.0),
+            3 => self.jitter = value.clamp(0.0, 1.0),
+            4 => self.mix.dry = value.min(1.0),
+            5 => self.mix.wet = value.min(1.0),
+            _ => console::error_1(&format!("Cannot update bitcrusher {}", param).into()),
+        }
+    }
+
+    fn quantize(&self, value: f32) -> f32 {
+        let levels = 2f32.powf(self.bits - 1.0);
+        let noise = if self.dither > 0.0 {
+            RANDOM.with(|r| {
+                let mut r = r.borrow_mut();
+                (r.next_f32() - r.next_f32()) * self.dither
+            })
+        } else {
+            0.0
+        };
+
+        ((value * levels + noise).round() / levels).clamp(-1.0, 1.0)
+    }
+}
+
+impl EffectTrait for Bitcrusher {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn process(&mut self, sample_l: &mut f32, sample_r: &mut f32) {
+        self.phase += self.rate / SAMPLE_RATE;
+
+        // Nouvel échantillon bloqué à chaque période de l'horloge simulée
+        if self.phase >= self.threshold {
+            self.phase -= self.threshold;
+            self.held = [self.quantize(*sample_l), self.quantize(*sample_r)];
+
+            self.threshold = if self.jitter > 0.0 {
+                1.0 + self.jitter * 0.5 * RANDOM.with(|r| r.borrow_mut().next_bipolar())
+            } else {
+                1.0
+            };
+        }
+
+        *sample_l = self.mix.dry * *sample_l + self.mix.wet * self.held[0];
+        *sample_r = self.mix.dry * *sample_r + self.mix.wet * self.held[1];
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,448 @@
+use std::any::Any;
+
+use web_sys::console;
+
+use crate::{
+    global::SAMPLE_MANAGER,
+    sound_engine::dsp::{
+        fft::{Complex, Fft},
+        fx::EffectTrait,
+    },
+    utils::{constants::SAMPLE_RATE, types::Mix},
+};
+
+/// Début de la réponse traité en convolution directe, sans latence
+const HEAD_LENGTH: usize = PARTITION_SIZES[0];
+/// Tailles de partition : le premier étage couvre la réponse à partir de sa taille et
+/// rend son bloc dès la frontière ; les suivants, retardés d'un bloc pour étaler leur
+/// calcul, la couvrent à partir du double de leur taille. Chaque étage s'arrête où
+/// commence le suivant.
+const PARTITION_SIZES: [usize; 3] = [64, 512, 4096];
+/// Assez long pour qu'un étage retardé relise ses entrées jusqu'à la fin de son bloc
+const HISTORY_LENGTH: usize = 4 * PARTITION_SIZES[PARTITION_SIZES.len() - 1];
+const MAX_IMPULSE_SECONDS: f32 = 10.0;
+/// Valeurs complexes traitées par échantillon pour préparer une nouvelle réponse
+/// (une passe de FFT de taille N en compte N), l'ancienne restant active en attendant
+const BUILD_BUDGET_PER_SAMPLE: usize = 512;
+
+/// Sépare le spectre d'un signal stéréo transformé d'un bloc (gauche en partie réelle,
+/// droite en partie imaginaire) en spectres gauche et droit
+fn split_spectrum(packed: &[Complex], left: &mut [Complex], right: &mut [Complex]) {
+    let size = packed.len();
+    for k in 0..size {
+        let a = packed[k];
+        let b = packed[(size - k) % size].conj();
+        left[k] = (a + b).scale(0.5);
+        right[k] = (a - b).rotate().scale(-0.5);
+    }
+}
+
+/// Étage de convolution partitionnée uniforme (overlap-save). Le calcul d'un bloc est
+/// découpé en étapes de coût voisin : chargement des entrées, passes de la FFT,
+/// produits par demi-spectre de chaque partition, passes de la FFT inverse, recopie.
+/// Un étage direct les enchaîne à la frontière de bloc ; un étage retardé les répartit
+/// sur le bloc suivant, sa réponse commençant un bloc plus tard.
+struct Stage {
+    size: usize,
+    delayed: bool,
+    /// Partie de la réponse couverte
+    start: usize,
+    end: usize,
+    partitions: usize,
+    fft: Fft,
+    impulse: [Vec<Vec<Complex>>; 2],
+    /// Ligne de retard fréquentielle : spectres des derniers blocs d'entrée
+    spectra: [Vec<Vec<Complex>>; 2],
+    ring_index: usize,
+    window: Vec<Complex>,
+    accumulator: [Vec<Complex>; 2],
+    /// Sortie lue pendant le bloc courant
+    output: [Vec<f32>; 2],
+    /// Sortie en cours de calcul
+    pending: [Vec<f32>; 2],
+    block_start: usize,
+    step: usize,
+}
+
+impl Stage {
+    /// Les spectres des partitions sont ensuite calculés par `build_step`
+    fn new(size: usize, delayed: bool, start: usize, end: usize) -> Self {
+        let partitions = (end - start).div_ceil(size);
+
+        Self {
+            size,
+            delayed,
+            start,
+            end,
+            partitions,
+            fft: Fft::new(size * 2),
+            impulse: [
+                Vec::with_capacity(partitions),
+                Vec::with_capacity(partitions),
+            ],
+            spectra: [
+                Vec::with_capacity(partitions),
+                Vec::with_capacity(partitions),
+            ],
+            ring_index: 0,
+            window: vec![Complex::ZERO; size * 2],
+            accumulator: [vec![Complex::ZERO; size * 2], vec![Complex::ZERO; size * 2]],
+            output: [vec![0.0; size], vec![0.0; size]],
+            pending: [vec![0.0; size], vec![0.0; size]],
+            block_start: 0,
+            step: 0,
+        }
+    }
+
+    fn is_built(&self) -> bool {
+        self.impulse[0].len() == self.partitions
+    }
+
+    /// Étape `step` du spectre de la partition suivante : chargement, passes de la FFT,
+    /// puis séparation des canaux. Renvoie vrai quand la partition est terminée.
+    fn build_step(&mut self, impulse: &[Vec<f32>; 2], step: usize, energy: &mut [f32; 2]) -> bool {
+        let passes = self.fft.passes();
+
+        if step == 0 {
+            let from = self.start + self.impulse[0].len() * self.size;
+            self.window.fill(Complex::ZERO);
+            for (i, value) in self.window.iter_mut().take(self.size).enumerate() {
+                let index = from + i;
+                if index < self.end {
+                    let (left, right) = (impulse[0][index], impulse[1][index]);
+                    energy[0] += left * left;
+                    energy[1] += right * right;
+                    *value = Complex::new(left, right);
+                }
+            }
+            self.fft.bit_reverse(&mut self.window);
+        } else if step <= passes {
+            self.fft.pass(&mut self.window, step - 1);
+        } else {
+            let block = self.size * 2;
+            let mut left = vec![Complex::ZERO; block];
+            let mut right = vec![Complex::ZERO; block];
+            split_spectrum(&self.window, &mut left, &mut right);
+            self.impulse[0].push(left);
+            self.impulse[1].push(right);
+            self.spectra[0].push(vec![Complex::ZERO; block]);
+            self.spectra[1].push(vec![Complex::ZERO; block]);
+            return true;
+        }
+
+        false
+    }
+
+    fn steps(&self) -> usize {
+        2 * self.fft.passes() + 2 * self.partitions + 4
+    }
+
+    /// Avance l'étage d'un échantillon, `time` étant l'instant dont l'entrée n'est pas
+    /// encore écrite dans l'historique
+    fn tick(&mut self, history: &[Vec<f32>; 2], time: usize) {
+        let offset = time % self.size;
+        let steps = self.steps();
+
+        if offset == 0 {
+            if self.delayed {
+                std::mem::swap(&mut self.output, &mut self.pending);
+            }
+            self.block_start = time;
+            self.step = 0;
+        }
+
+        // Réparti uniformément : toutes les étapes sont faites au dernier échantillon
+        let target = if self.delayed {
+            (offset + 1) * steps / self.size
+        } else {
+            steps
+        };
+        while self.step < target {
+            self.compute_step(history, self.step);
+            self.step += 1;
+        }
+
+        if !self.delayed && offset == 0 {
+            std::mem::swap(&mut self.output, &mut self.pending);
+        }
+    }
+
+    /// Étape `step` du calcul des `size` échantillons de sortie à partir des entrées
+    /// écrites avant `block_start`
+    fn compute_step(&mut self, history: &[Vec<f32>; 2], step: usize) {
+        let block = self.size * 2;
+        let passes = self.fft.passes();
+        let products = passes + 2;
+        let inverse = products + 2 * self.partitions;
+
+        if step == 0 {
+            for (m, value) in self.window.iter_mut().enumerate() {
+                let index = (self.block_start + HISTORY_LENGTH - block + m) % HISTORY_LENGTH;
+                *value = Complex::new(history[0][index], history[1][index]);
+            }
+            self.fft.bit_reverse(&mut self.window);
+        } else if step <= passes {
+            self.fft.pass(&mut self.window, step - 1);
+        } else if step < products {
+            self.ring_index = (self.ring_index + self.partitions - 1) % self.partitions;
+            let [spectra_l, spectra_r] = &mut self.spectra;
+            split_spectrum(
+                &self.window,
+                &mut spectra_l[self.ring_index],
+                &mut spectra_r[self.ring_index],
+            );
+            self.accumulator
+                .iter_mut()
+                .for_each(|a| a.fill(Complex::ZERO));
+        } else if step < inverse {
+            // La partition p utilise le spectre calculé p blocs plus tôt
+            let p = (step - products) / 2;
+            let first_bin = (step - products) % 2 * self.size;
+            let bins = first_bin..first_bin + self.size;
+            let ring = (self.ring_index + p) % self.partitions;
+            for channel in 0..2 {
+                let spectrum = &self.spectra[channel][ring];
+                let impulse = &self.impulse[channel][p];
+                for k in bins.clone() {
+                    self.accumulator[channel][k] += spectrum[k] * impulse[k];
+                }
+            }
+        } else if step == inverse {
+            // Les deux sorties sont réelles : une seule transformée inverse suffit,
+            // calculée comme la conjuguée de la transformée directe du conjugué
+            for k in 0..block {
+                self.window[k] = (self.accumulator[0][k] + self.accumulator[1][k].rotate()).conj();
+            }
+            self.fft.bit_reverse(&mut self.window);
+        } else if step <= inverse + passes {
+            self.fft.pass(&mut self.window, step - inverse - 1);
+        } else {
+            let scale = 1.0 / block as f32;
+            for n in 0..self.size {
+                let value = self.window[self.size + n].conj().scale(scale);
+                self.pending[0][n] = value.re;
+                self.pending[1][n] = value.im;
+            }
+        }
+    }
+}
+
+/// Réponse en cours de préparation, mise en place une fois tous ses étages calculés
+struct PendingImpulse {
+    impulse: [Vec<f32>; 2],
+    stages: Vec<Stage>,
+    /// Étape en cours dans la partition suivante de l'étage à compléter
+    step: usize,
+    energy: [f32; 2],
+    budget: usize,
+}
+
+impl PendingImpulse {
+    fn new(impulse: [Vec<f32>; 2]) -> Self {
+        let length = impulse[0].len().min(impulse[1].len());
+
+        let mut stages = Vec::new();
+        for (i, &size) in PARTITION_SIZES.iter().enumerate() {
+            let start = if i == 0 { size } else { 2 * size };
+            let end = match PARTITION_SIZES.get(i + 1) {
+                Some(&next) => (2 * next).min(length),
+                None => length,
+            };
+            if end > start {
+                stages.push(Stage::new(size, i > 0, start, end));
+            }
+        }
+
+        let mut energy = [0.0; 2];
+        for (channel, value) in energy.iter_mut().enumerate() {
+            *value = impulse[channel][..length.min(HEAD_LENGTH)]
+                .iter()
+                .map(|v| v * v)
+                .sum();
+        }
+
+        Self {
+            impulse,
+            stages,
+            step: 0,
+            energy,
+            budget: 0,
+        }
+    }
+
+    /// Dépense le budget d'un échantillon, renvoie vrai quand la réponse est prête
+    fn advance(&mut self) -> bool {
+        self.budget += BUILD_BUDGET_PER_SAMPLE;
+
+        while let Some(stage) = self.stages.iter_mut().find(|stage| !stage.is_built()) {
+            let cost = stage.size * 2;
+            if self.budget < cost {
+                return false;
+            }
+            self.budget -= cost;
+
+            if stage.build_step(&self.impulse, self.step, &mut self.energy) {
+                self.step = 0;
+            } else {
+                self.step += 1;
+            }
+        }
+
+        true
+    }
+}
+
+/// Réverbération à convolution : la réponse impulsionnelle est un sample chargé dans le
+/// `SampleManager` (stéréo si le sample a deux canaux). Le début de la réponse est
+/// convolué directement, la suite par des étages partitionnés de tailles croissantes.
+pub struct Convolution {
+    id: usize,
+    /// Sample utilisé comme réponse, éventuellement pas encore reçu
+    pub impulse_id: Option<u32>,
+    pub mix: Mix,
+
+    head: [Vec<f32>; 2],
+    stages: Vec<Stage>,
+    /// Normalisation en énergie de la réponse
+    gain: f32,
+    pending: Option<PendingImpulse>,
+    history: [Vec<f32>; 2],
+    time: usize,
+}
+
+impl Convolution {
+    pub fn new(id: usize) -> Self {
+        Self {
+            id,
+            impulse_id: None,
+            mix: Mix { dry: 1.0, wet: 0.3 },
+            head: [Vec::new(), Vec::new()],
+            stages: Vec::new(),
+            gain: 0.0,
+            pending: None,
+            history: [vec![0.0; HISTORY_LENGTH], vec![0.0; HISTORY_LENGTH]],
+            time: 0,
+        }
+    }
+
+    /// Paramètres : 0 id du sample de réponse (négatif pour la retirer), 1 dry, 2 wet
+    pub fn update(&mut self, param: u32, value: f32) {
+        match param {
+            0 if value < 0.0 => {
+                self.impulse_id = None;
+                self.set_impulse([Vec::new(), Vec::new()]);
+            }
+            0 => {
+                self.impulse_id = Some(value as u32);
+                self.load_impulse();
+            }
+            1 => self.mix.dry = value.min(1.0),
+            2 => self.mix.wet = value.min(1.0),
+            _ => console::error_1(&format!("Cannot update convolution {}", param).into()),
+        }
+    }
+
+    /// Appelé quand un sample arrive, la réponse pouvant être demandée avant son envoi
+    pub fn sample_loaded(&mut self, sample_id: u32) {
+        if self.impulse_id == Some(sample_id) {
+            self.load_impulse();
+        }
+    }
+
+    fn load_impulse(&mut self) {
+        let Some(sample_id) = self.impulse_id else {
+            return;
+        };
+
+        let impulse = SAMPLE_MANAGER.with(|sm| {
+            let sm = sm.lock().unwrap();
+            let sample = sm.get_sample(sample_id)?;
+            let max_length = (MAX_IMPULSE_SECONDS * SAMPLE_RATE) as usize;
+
+            let (left, right) = if sample.channels == 2 {
+                sample.values.split_at(sample.values.len() / 2)
+            } else {
+                (&sample.values[..], &sample.values[..])
+            };
+            let left = &left[..left.len().min(max_length)];
+            let right = &right[..right.len().min(max_length)];
+
+            Some([left.to_vec(), right.to_vec()])
+        });
+
+        if let Some(impulse) = impulse {
+            self.set_impulse(impulse);
+        }
+    }
+
+    /// Lance la préparation de la réponse, dont les spectres sont calculés au fil de
+    /// `process` pour ne pas bloquer le rendu
+    fn set_impulse(&mut self, impulse: [Vec<f32>; 2]) {
+        self.pending = Some(PendingImpulse::new(impulse));
+    }
+
+    /// Remplace la réponse active par la réponse préparée, normalisée en énergie
+    fn install_impulse(&mut self, pending: PendingImpulse) {
+        let energy = pending.energy[0].max(pending.energy[1]);
+        self.gain = if energy > 0.0 {
+            energy.sqrt().recip()
+        } else {
+            0.0
+        };
+
+        let length = pending.impulse[0].len().min(pending.impulse[1].len());
+        self.head = pending
+            .impulse
+            .map(|channel| channel[..length.min(HEAD_LENGTH)].to_vec());
+        self.stages = pending.stages;
+
+        self.history.iter_mut().for_each(|h| h.fill(0.0));
+        self.time = 0;
+    }
+}
+
+impl EffectTrait for Convolution {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn process(&mut self, sample_l: &mut f32, sample_r: &mut f32) {
+        if self
+            .pending
+            .as_mut()
+            .is_some_and(|pending| pending.advance())
+            && let Some(pending) = self.pending.take()
+        {
+            self.install_impulse(pending);
+        }
+
+        for stage in &mut self.stages {
+            stage.tick(&self.history, self.time);
+        }
+
+        let index = self.time % HISTORY_LENGTH;
+        self.history[0][index] = *sample_l;
+        self.history[1][index] = *sample_r;
+
+        let mut wet = [0.0; 2];
+        for (channel, value) in wet.iter_mut().enumerate() {
+            for (i, tap) in self.head[channel].iter().enumerate() {
+                *value +=
+                    tap * self.history[channel][(index + HISTORY_LENGTH - i) % HISTORY_LENGTH];
+            }
+            for stage in &self.stages {
+                *value += stage.output[channel][self.time % stage.size];
+            }
+            *value *= self.gain;
+        }
+
+        self.time = (self.time + 1) % HISTORY_LENGTH;
+
+        *sample_l = self.mix.dry * *sample_l + self.mix.wet * wet[0];
+        *sample_r = self.mix.dry * *sample_r + self.mix.wet * wet[1];
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,256 @@
+use std::{any::Any, f32::consts::PI};
+
+use web_sys::console;
+
+use crate::{
+    sound_engine::dsp::fx::EffectTrait,
+    utils::{constants::SAMPLE_RATE, types::Mix},
+};
+
+/// Coefficients du filtre anti-repliement par phase du suréchantillonnage
+const TAPS_PER_PHASE: usize = 16;
+const MAX_OVERSAMPLING: usize = 8;
+const MAX_DRIVE_DB: f32 = 48.0;
+/// Points de la courbe personnalisée, répartis uniformément entre -1 et 1
+const TRANSFER_TABLE_SIZE: usize = 17;
+/// Les paramètres à partir de cet index écrivent les points de la courbe personnalisée
+const TABLE_PARAM_OFFSET: u32 = 16;
+
+#[derive(Clone, Copy)]
+pub enum DistortionCurve {
+    SoftClip,
+    HardClip,
+    Foldback,
+    Tube,
+    Table,
+}
+
+impl TryFrom<u8> for DistortionCurve {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(DistortionCurve::SoftClip),
+            1 => Ok(DistortionCurve::HardClip),
+            2 => Ok(DistortionCurve::Foldback),
+            3 => Ok(DistortionCurve::Tube),
+            4 => Ok(DistortionCurve::Table),
+            _ => Err("Courbe de distorsion inconnue"),
+        }
+    }
+}
+
+/// Suréchantillonnage par un facteur entier : interpolation polyphase vers le haut,
+/// puis filtrage et décimation vers le bas avec le même noyau sinc fenêtré
+struct Oversampler {
+    factor: usize,
+    kernel: Vec<f32>,
+    input_history: [f32; TAPS_PER_PHASE],
+    input_index: usize,
+    output_history: Vec<f32>,
+    output_index: usize,
+}
+
+impl Oversampler {
+    fn new(factor: usize) -> Self {
+        let length = TAPS_PER_PHASE * factor;
+        // Coupure un peu sous la fréquence de Nyquist d'origine
+        let cutoff = 0.45 / factor as f32;
+        let center = (length - 1) as f32 / 2.0;
+
+        let mut kernel: Vec<f32> = (0..length)
+            .map(|i| {
+                let x = i as f32 - center;
+                let sinc = if x == 0.0 {
+                    2.0 * cutoff
+                } else {
+                    (2.0 * PI * cutoff * x).sin() / (PI * x)
+                };
+                // Fenêtre de Blackman
+                let phase = 2.0 * PI * i as f32 / (length - 1) as f32;
+                sinc * (0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos())
+            })
+            .collect();
+
+        let sum: f32 = kernel.iter().sum();
+        kernel.iter_mut().for_each(|k| *k /= sum);
+
+        Self {
+            factor,
+            kernel,
+            input_history: [0.0; TAPS_PER_PHASE],
+            input_index: 0,
+            output_history: vec![0.0; length],
+            output_index: 0,
+        }
+    }
+
+    fn upsample(&mut self, input: f32, output: &mut [f32]) {
+        self.input_index = (self.input_index + 1) % TAPS_PER_PHASE;
+        self.input_history[self.input_index] = input;
+
+        for (phase, value) in output.iter_mut().take(self.factor).enumerate() {
+            let mut sum = 0.0;
+            for k in 0..TAPS_PER_PHASE {
+                let index = (self.input_index + TAPS_PER_PHASE - k) % TAPS_PER_PHASE;
+                sum += self.kernel[k * self.factor + phase] * self.input_history[index];
+            }
+            // Compense les zéros insérés entre les échantillons
+            *value = sum * self.factor as f32;
+        }
+    }
+
+    fn downsample(&mut self, input: &[f32]) -> f32 {
+        let length = self.kernel.len();
+        for &value in input.iter().take(self.factor) {
+            self.output_index = (self.output_index + 1) % length;
+            self.output_history[self.output_index] = value;
+        }
+
+        self.kernel
+            .iter()
+            .enumerate()
+            .map(|(j, k)| k * self.output_history[(self.output_index + length - j) % length])
+            .sum()
+    }
+}
+
+/// Saturation par table de transfert, calculée à une fréquence suréchantillonnée
+/// (1, 2, 4 ou 8 fois) pour limiter le repliement des harmoniques
+pub struct Distortion {
+    id: usize,
+    pub curve: DistortionCurve,
+    /// Gain d'entrée en dB
+    pub drive: f32,
+    /// Décalage avant la courbe, qui ajoute des harmoniques paires
+    pub bias: f32,
+    /// Fréquence de coupure du passe-bas de sortie, en Hz
+    pub tone: f32,
+    pub oversampling: usize,
+    pub mix: Mix,
+    pub table: [f32; TRANSFER_TABLE_SIZE],
+
+    oversamplers: [Oversampler; 2],
+    tone_states: [f32; 2],
+    dc_states: [(f32, f32); 2],
+}
+
+impl Distortion {
+    pub fn new(id: usize) -> Self {
+        let oversampling = 4;
+        // Par défaut, la courbe personnalisée reprend la saturation douce
+        let table = std::array::from_fn(|i| {
+            let x = 2.0 * i as f32 / (TRANSFER_TABLE_SIZE - 1) as f32 - 1.0;
+            (2.0 * x).tanh() / 2f32.tanh()
+        });
+
+        Self {
+            id,
+            curve: DistortionCurve::SoftClip,
+            drive: 12.0,
+            bias: 0.0,
+            tone: 12_000.0,
+            oversampling,
+            mix: Mix { dry: 0.0, wet: 1.0 },
+            table,
+            oversamplers: [
+                Oversampler::new(oversampling),
+                Oversampler::new(oversampling),
+            ],
+            tone_states: [0.0; 2],
+            dc_states: [(0.0, 0.0); 2],
+        }
+    }
+
+    /// Paramètres : 0 courbe, 1 drive (dB), 2 bias, 3 tonalité (Hz), 4 suréchantillonnage,
+    /// 5 dry, 6 wet, 16 et suivants : points de la courbe personnalisée
+    pub fn update(&mut self, param: u32, value: f32) {
+        match param {
+            0 => match DistortionCurve::try_from(value as u8) {
+                Ok(curve) => self.curve = curve,
+                Err(e) => console::error_1(&e.into()),
+            },
+            1 => self.drive = value.clamp(0.0, MAX_DRIVE_DB),
+            2 => self.bias = value.clamp(-1.0, 1.0),
+            3 => self.tone = value.clamp(200.0, 20_000.0),
+            4 => {
+                let factor = (value as usize)
+                    .clamp(1, MAX_OVERSAMPLING)
+                    .next_power_of_two();
+                if factor != self.oversampling {
+                    self.oversampling = factor;
+                    self.oversamplers = [Oversampler::new(factor), Oversampler::new(factor)];
+                }
+            }
+            5 => self.mix.dry = value.min(1.0),
+            6 => self.mix.wet = value.min(1.0),
+            p if p >= TABLE_PARAM_OFFSET
+                && ((p - TABLE_PARAM_OFFSET) as usize) < TRANSFER_TABLE_SIZE =>
+            {
+                self.table[(p - TABLE_PARAM_OFFSET) as usize] = value.clamp(-1.0, 1.0);
+            }
+            _ => console::error_1(&format!("Cannot update distortion {}", param).into()),
+        }
+    }
+
+    fn shape(&self, x: f32) -> f32 {
+        match self.curve {
+            DistortionCurve::SoftClip => x.tanh(),
+            DistortionCurve::HardClip => x.clamp(-1.0, 1.0),
+            DistortionCurve::Foldback => {
+                // Replie le signal en triangle dès qu'il dépasse ±1
+                let t = (x + 1.0).rem_euclid(4.0);
+                if t < 2.0 { t - 1.0 } else { 3.0 - t }
+            }
+            DistortionCurve::Tube => {
+                // Alternance négative écrêtée plus tôt, comme une triode
+                if x >= 0.0 {
+                    x.tanh()
+                } else {
+                    0.7 * (x / 0.7).tanh()
+                }
+            }
+            DistortionCurve::Table => {
+                let position = (x.clamp(-1.0, 1.0) + 1.0) * 0.5 * (TRANSFER_TABLE_SIZE - 1) as f32;
+                let index = (position.floor() as usize).min(TRANSFER_TABLE_SIZE - 2);
+                let frac = position - index as f32;
+                self.table[index] + (self.table[index + 1] - self.table[index]) * frac
+            }
+        }
+    }
+}
+
+impl EffectTrait for Distortion {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn process(&mut self, sample_l: &mut f32, sample_r: &mut f32) {
+        let gain = 10f32.powf(self.drive / 20.0);
+        let offset = self.shape(self.bias);
+        let factor = self.oversampling;
+        let tone = 1.0 - (-2.0 * PI * self.tone / SAMPLE_RATE).exp();
+
+        for (channel, sample) in [sample_l, sample_r].into_iter().enumerate() {
+            let mut buffer = [0.0; MAX_OVERSAMPLING];
+            self.oversamplers[channel].upsample(*sample, &mut buffer);
+            for value in buffer.iter_mut().take(factor) {
+                *value = self.shape(*value * gain + self.bias) - offset;
+            }
+            let shaped = self.oversamplers[channel].downsample(&buffer);
+
+            self.tone_states[channel] += (shaped - self.tone_states[channel]) * tone;
+
+            // Coupe-continu : le bias décale la moyenne des courbes asymétriques
+            let (last_input, last_output) = self.dc_states[channel];
+            let wet = self.tone_states[channel] - last_input + 0.995 * last_output;
+            self.dc_states[channel] = (self.tone_states[channel], wet);
+
+            *sample = self.mix.dry * *sample + self.mix.wet * wet;
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,367 @@
+use std::{any::Any, collections::VecDeque};
+
+use web_sys::console;
+
+use crate::{sound_engine::dsp::fx::EffectTrait, utils::constants::SAMPLE_RATE};
+
+const MAX_LOOKAHEAD_MS: f32 = 10.0;
+/// Plancher des niveaux convertis en dB, pour éviter -inf sur le silence
+const MIN_LEVEL_DB: f32 = -120.0;
+
+fn to_db(amplitude: f32) -> f32 {
+    (20.0 * amplitude.abs().log10()).max(MIN_LEVEL_DB)
+}
+
+fn from_db(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Coefficient d'un lissage à un pôle atteignant ~63 % de la cible en `ms`
+fn time_coefficient(ms: f32) -> f32 {
+    if ms <= 0.0 {
+        0.0
+    } else {
+        (-1.0 / (ms * 0.001 * SAMPLE_RATE)).exp()
+    }
+}
+
+/// Compresseur à détection directe : la réduction de gain est calculée en dB
+/// avec un genou souple, puis lissée par les temps d'attaque et de relâchement
+pub struct Compressor {
+    id: usize,
+    /// Seuil en dBFS
+    pub threshold: f32,
+    pub ratio: f32,
+    /// Largeur du genou en dB, 0 pour un genou franc
+    pub knee: f32,
+    pub attack: f32,
+    pub release: f32,
+    /// Gain de compensation en dB
+    pub makeup: f32,
+    /// Couplage stéréo : 0 canaux indépendants, 1 même réduction sur les deux
+    pub link: f32,
+
+    attack_coefficient: f32,
+    release_coefficient: f32,
+    reductions: [f32; 2],
+}
+
+impl Compressor {
+    pub fn new(id: usize) -> Self {
+        let attack = 10.0;
+        let release = 100.0;
+        Self {
+            id,
+            threshold: -18.0,
+            ratio: 4.0,
+            knee: 6.0,
+            attack,
+            release,
+            makeup: 0.0,
+            link: 1.0,
+            attack_coefficient: time_coefficient(attack),
+            release_coefficient: time_coefficient(release),
+            reductions: [0.0; 2],
+        }
+    }
+
+    /// Paramètres : 0 seuil (dB), 1 ratio, 2 genou (dB), 3 attaque (ms),
+    /// 4 relâchement (ms), 5 compensation (dB), 6 couplage stéréo
+    pub fn update(&mut self, param: u32, value: f32) {
+        match param {
+            0 => self.threshold = value.clamp(-60.0, 0.0),
+            1 => self.ratio = value.clamp(1.0, 100.0),
+            2 => self.knee = value.clamp(0.0, 24.0),
+            3 => {
+                self.attack = value.clamp(0.0, 500.0);
+                self.attack_coefficient = time_coefficient(self.attack);
+            }
+            4 => {
+                self.release = value.clamp(1.0, 5000.0);
+                self.release_coefficient = time_coefficient(self.release);
+            }
+            5 => self.makeup = value.clamp(0.0, 40.0),
+            6 => self.link = value.clamp(0.0, 1.0),
+            _ => console::error_1(&format!("Cannot update compressor {}", param).into()),
+        }
+    }
+
+    /// Réduction statique en dB pour un niveau d'entrée en dB
+    fn compute_reduction(&self, level: f32) -> f32 {
+        let over = level - self.threshold;
+        let slope = 1.0 / self.ratio - 1.0;
+
+        if 2.0 * over < -self.knee {
+            0.0
+        } else if self.knee > 0.0 && 2.0 * over.abs() <= self.knee {
+            -slope * (over + self.knee / 2.0).powi(2) / (2.0 * self.knee)
+        } else {
+            -slope * over
+        }
+    }
+}
+
+impl EffectTrait for Compressor {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn process(&mut self, sample_l: &mut f32, sample_r: &mut f32) {
+        let peak = sample_l.abs().max(sample_r.abs());
+
+        for (channel, sample) in [sample_l, sample_r].into_iter().enumerate() {
+            let level = to_db(self.link * peak + (1.0 - self.link) * sample.abs());
+            let target = self.compute_reduction(level);
+
+            let coefficient = if target > self.reductions[channel] {
+                self.attack_coefficient
+            } else {
+                self.release_coefficient
+            };
+            self.reductions[channel] = target + (self.reductions[channel] - target) * coefficient;
+
+            *sample *= from_db(self.makeup - self.reductions[channel]);
+        }
+    }
+
+    fn gain_reduction(&self) -> Option<f32> {
+        Some(self.reductions[0].max(self.reductions[1]))
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Limiteur à anticipation : le gain nécessaire est pris au minimum sur la fenêtre
+/// d'anticipation puis moyenné sur la même durée, ce qui garantit que le signal
+/// retardé ne dépasse jamais le plafond tout en évitant les sauts de gain
+pub struct Limiter {
+    id: usize,
+    /// Plafond de sortie en dBFS
+    pub ceiling: f32,
+    /// Gain d'entrée en dB
+    pub input_gain: f32,
+    pub release: f32,
+    pub lookahead: f32,
+
+    release_coefficient: f32,
+    window: usize,
+    delay: VecDeque<(f32, f32)>,
+    /// Minimum glissant des gains nécessaires : (instant, gain), les instants
+    /// bouclant sur `usize` et étant comparés par différence
+    minimum: VecDeque<(usize, f32)>,
+    averaging: VecDeque<f32>,
+    sum: f64,
+    time: usize,
+    gain: f32,
+}
+
+impl Limiter {
+    pub fn new(id: usize) -> Self {
+        let release = 50.0;
+        let mut limiter = Self {
+            id,
+            ceiling: -0.3,
+            input_gain: 0.0,
+            release,
+            lookahead: 5.0,
+            release_coefficient: time_coefficient(release),
+            window: 1,
+            delay: VecDeque::new(),
+            minimum: VecDeque::new(),
+            averaging: VecDeque::new(),
+            sum: 0.0,
+            time: 0,
+            gain: 1.0,
+        };
+        limiter.resize_window();
+        limiter
+    }
+
+    fn resize_window(&mut self) {
+        self.window = ((self.lookahead * 0.001 * SAMPLE_RATE) as usize).max(1);
+        self.delay = VecDeque::from(vec![(0.0, 0.0); self.window - 1]);
+        self.delay.reserve(1);
+        self.minimum = VecDeque::with_capacity(self.window + 1);
+        self.averaging = VecDeque::from(vec![1.0; self.window]);
+        self.averaging.reserve(1);
+        self.sum = self.window as f64;
+    }
+
+    /// Paramètres : 0 plafond (dB), 1 gain d'entrée (dB), 2 relâchement (ms),
+    /// 3 anticipation (ms)
+    pub fn update(&mut self, param: u32, value: f32) {
+        match param {
+            0 => self.ceiling = value.clamp(-24.0, 0.0),
+            1 => self.input_gain = value.clamp(0.0, 24.0),
+            2 => {
+                self.release = value.clamp(1.0, 1000.0);
+                self.release_coefficient = time_coefficient(self.release);
+            }
+            3 => {
+                self.lookahead = value.clamp(0.0, MAX_LOOKAHEAD_MS);
+                self.resize_window();
+            }
+            _ => console::error_1(&format!("Cannot update limiter {}", param).into()),
+        }
+    }
+}
+
+impl EffectTrait for Limiter {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn process(&mut self, sample_l: &mut f32, sample_r: &mut f32) {
+        let input_gain = from_db(self.input_gain);
+        let input = (*sample_l * input_gain, *sample_r * input_gain);
+
+        let ceiling = from_db(self.ceiling);
+        let peak = input.0.abs().max(input.1.abs());
+        let needed = if peak > ceiling { ceiling / peak } else { 1.0 };
+
+        while self.minimum.back().is_some_and(|&(_, g)| g >= needed) {
+            self.minimum.pop_back();
+        }
+        self.minimum.push_back((self.time, needed));
+        while self
+            .minimum
+            .front()
+            .is_some_and(|&(t, _)| self.time.wrapping_sub(t) >= self.window)
+        {
+            self.minimum.pop_front();
+        }
+        let minimum = self.minimum.front().map_or(1.0, |&(_, g)| g);
+
+        self.averaging.push_back(minimum);
+        self.sum += minimum as f64 - self.averaging.pop_front().unwrap_or(1.0) as f64;
+        let target = (self.sum / self.window as f64) as f32;
+
+        // Descente déjà lissée par la moyenne, remontée selon le relâchement
+        self.gain = if target < self.gain {
+            target
+        } else {
+            target + (self.gain - target) * self.release_coefficient
+        };
+
+        self.delay.push_back(input);
+        let (delayed_l, delayed_r) = self.delay.pop_front().unwrap_or((0.0, 0.0));
+        *sample_l = delayed_l * self.gain;
+        *sample_r = delayed_r * self.gain;
+
+        self.time = self.time.wrapping_add(1);
+    }
+
+    fn gain_reduction(&self) -> Option<f32> {
+        Some(-to_db(self.gain))
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Porte de bruit : s'ouvre quand l'enveloppe dépasse le seuil, se referme sous
+/// le seuil moins l'hystérésis une fois le maintien écoulé
+pub struct Gate {
+    id: usize,
+    pub threshold: f32,
+    /// Atténuation appliquée porte fermée, en dB négatifs
+    pub range: f32,
+    pub attack: f32,
+    pub hold: f32,
+    pub release: f32,
+    pub hysteresis: f32,
+
+    attack_coefficient: f32,
+    release_coefficient: f32,
+    envelope_coefficient: f32,
+    envelope: f32,
+    open: bool,
+    hold_remaining: usize,
+    gain: f32,
+}
+
+impl Gate {
+    pub fn new(id: usize) -> Self {
+        let attack = 1.0;
+        let release = 100.0;
+        Self {
+            id,
+            threshold: -50.0,
+            range: -80.0,
+            attack,
+            hold: 20.0,
+            release,
+            hysteresis: 4.0,
+            attack_coefficient: time_coefficient(attack),
+            release_coefficient: time_coefficient(release),
+            envelope_coefficient: time_coefficient(10.0),
+            envelope: 0.0,
+            open: false,
+            hold_remaining: 0,
+            gain: 0.0,
+        }
+    }
+
+    /// Paramètres : 0 seuil (dB), 1 plage (dB), 2 attaque (ms), 3 maintien (ms),
+    /// 4 relâchement (ms), 5 hystérésis (dB)
+    pub fn update(&mut self, param: u32, value: f32) {
+        match param {
+            0 => self.threshold = value.clamp(-90.0, 0.0),
+            1 => self.range = value.clamp(-120.0, 0.0),
+            2 => {
+                self.attack = value.clamp(0.0, 200.0);
+                self.attack_coefficient = time_coefficient(self.attack);
+            }
+            3 => self.hold = value.clamp(0.0, 2000.0),
+            4 => {
+                self.release = value.clamp(1.0, 5000.0);
+                self.release_coefficient = time_coefficient(self.release);
+            }
+            5 => self.hysteresis = value.clamp(0.0, 20.0),
+            _ => console::error_1(&format!("Cannot update gate {}", param).into()),
+        }
+    }
+}
+
+impl EffectTrait for Gate {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn process(&mut self, sample_l: &mut f32, sample_r: &mut f32) {
+        // Enveloppe crête : montée immédiate, descente sur 10 ms
+        let peak = sample_l.abs().max(sample_r.abs());
+        self.envelope = peak.max(self.envelope * self.envelope_coefficient);
+        let level = to_db(self.envelope);
+
+        if level > self.threshold {
+            self.open = true;
+            self.hold_remaining = (self.hold * 0.001 * SAMPLE_RATE) as usize;
+        } else if self.hold_remaining > 0 {
+            self.hold_remaining -= 1;
+        } else if level < self.threshold - self.hysteresis {
+            self.open = false;
+        }
+
+        let (target, coefficient) = if self.open {
+            (1.0, self.attack_coefficient)
+        } else {
+            (from_db(self.range), self.release_coefficient)
+        };
+        self.gain = target + (self.gain - target) * coefficient;
+
+        *sample_l *= self.gain;
+        *sample_r *= self.gain;
+    }
+
+    fn gain_reduction(&self) -> Option<f32> {
+        Some(-to_db(self.gain))
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
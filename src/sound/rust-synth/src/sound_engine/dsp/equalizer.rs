@@ -0,0 +1,131 @@
+use std::any::Any;
+
+use web_sys::console;
+
+use crate::sound_engine::dsp::fx::{BiquadFilter, EffectTrait, FilterType};
+
+const MAX_EQ_BANDS: usize = 8;
+/// Champs par bande : le paramètre vaut `bande * EQ_BAND_PARAMS + champ`
+const EQ_BAND_PARAMS: u32 = 8;
+/// Paramètre qui demande l'envoi de la courbe de réponse à l'interface
+const EQ_CURVE_PARAM: u32 = MAX_EQ_BANDS as u32 * EQ_BAND_PARAMS;
+const EQ_CURVE_POINTS: usize = 256;
+const EQ_CURVE_MIN_FREQUENCY: f32 = 20.0;
+const EQ_CURVE_MAX_FREQUENCY: f32 = 20_000.0;
+
+/// Préréglage des bandes : plateau grave, cloches réparties, plateau aigu
+const DEFAULT_BANDS: [(FilterType, f32); MAX_EQ_BANDS] = [
+    (FilterType::LowShelf, 80.0),
+    (FilterType::Bell, 200.0),
+    (FilterType::Bell, 500.0),
+    (FilterType::Bell, 1_000.0),
+    (FilterType::Bell, 2_500.0),
+    (FilterType::Bell, 5_000.0),
+    (FilterType::Bell, 8_000.0),
+    (FilterType::HighShelf, 12_000.0),
+];
+
+struct EqBand {
+    filter: BiquadFilter,
+    enabled: bool,
+}
+
+/// Égaliseur paramétrique : jusqu'à huit bandes biquad en série, chacune avec son
+/// type, sa fréquence, son gain, son Q et son activation
+pub struct Equalizer {
+    id: usize,
+    bands: Vec<EqBand>,
+    /// Posé par le paramètre de courbe, consommé par `take_curve`
+    pub curve_requested: bool,
+}
+
+impl Equalizer {
+    pub fn new(id: usize) -> Self {
+        let bands = DEFAULT_BANDS
+            .iter()
+            .map(|&(filter_type, frequency)| EqBand {
+                filter: BiquadFilter::new(frequency, 0.7, id, filter_type as u8, 0.0),
+                enabled: false,
+            })
+            .collect();
+
+        Self {
+            id,
+            bands,
+            curve_requested: false,
+        }
+    }
+
+    /// Paramètres : `bande * 8 + champ`, champs 0 type, 1 fréquence (Hz), 2 gain (dB),
+    /// 3 Q, 4 activation ; 64 demande la courbe de réponse
+    pub fn update(&mut self, param: u32, value: f32) {
+        if param == EQ_CURVE_PARAM {
+            self.curve_requested = true;
+            return;
+        }
+
+        let Some(band) = self.bands.get_mut((param / EQ_BAND_PARAMS) as usize) else {
+            console::error_1(&format!("Cannot update equalizer {}", param).into());
+            return;
+        };
+        let filter = &mut band.filter;
+
+        match param % EQ_BAND_PARAMS {
+            0 => filter.edit(filter.frequency, filter.q, value as u8, filter.gain),
+            1 => filter.edit(value, filter.q, filter.filter_type, filter.gain),
+            2 => filter.edit(
+                filter.frequency,
+                filter.q,
+                filter.filter_type,
+                value.clamp(-24.0, 24.0),
+            ),
+            3 => filter.edit(filter.frequency, value, filter.filter_type, filter.gain),
+            4 => band.enabled = value != 0.0,
+            _ => console::error_1(&format!("Cannot update equalizer {}", param).into()),
+        }
+    }
+
+    /// Réponse cumulée des bandes actives en dB, pour des fréquences réparties
+    /// logarithmiquement de 20 Hz à 20 kHz : paires (fréquence, gain) en f32 little-endian
+    pub fn take_curve(&mut self) -> Option<Vec<u8>> {
+        if !self.curve_requested {
+            return None;
+        }
+        self.curve_requested = false;
+
+        let ratio = EQ_CURVE_MAX_FREQUENCY / EQ_CURVE_MIN_FREQUENCY;
+        let mut bytes = Vec::with_capacity(EQ_CURVE_POINTS * 8);
+
+        for i in 0..EQ_CURVE_POINTS {
+            let frequency =
+                EQ_CURVE_MIN_FREQUENCY * ratio.powf(i as f32 / (EQ_CURVE_POINTS - 1) as f32);
+            let magnitude: f32 = self
+                .bands
+                .iter()
+                .filter(|band| band.enabled)
+                .map(|band| band.filter.magnitude(frequency))
+                .product();
+
+            bytes.extend_from_slice(&frequency.to_le_bytes());
+            bytes.extend_from_slice(&(20.0 * magnitude.max(1e-6).log10()).to_le_bytes());
+        }
+
+        Some(bytes)
+    }
+}
+
+impl EffectTrait for Equalizer {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn process(&mut self, sample_l: &mut f32, sample_r: &mut f32) {
+        for band in self.bands.iter_mut().filter(|band| band.enabled) {
+            band.filter.process(sample_l, sample_r);
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
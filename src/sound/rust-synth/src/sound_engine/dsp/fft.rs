@@ -0,0 +1,129 @@
+use std::ops::{Add, AddAssign, Mul, Sub};
+
+#[derive(Clone, Copy, Default)]
+pub struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub const ZERO: Complex = Complex { re: 0.0, im: 0.0 };
+
+    pub fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub fn scale(self, factor: f32) -> Self {
+        Self::new(self.re * factor, self.im * factor)
+    }
+
+    /// Multiplication par i
+    pub fn rotate(self) -> Self {
+        Self::new(-self.im, self.re)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl AddAssign for Complex {
+    fn add_assign(&mut self, other: Complex) {
+        self.re += other.re;
+        self.im += other.im;
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+/// FFT radix-2 en place, tables précalculées pour une taille fixe (puissance de deux)
+pub struct Fft {
+    size: usize,
+    twiddles: Vec<Complex>,
+    reversed: Vec<usize>,
+}
+
+impl Fft {
+    pub fn new(size: usize) -> Self {
+        debug_assert!(size.is_power_of_two());
+        let bits = size.trailing_zeros();
+
+        let twiddles = (0..size / 2)
+            .map(|k| {
+                let angle = -2.0 * std::f64::consts::PI * k as f64 / size as f64;
+                Complex::new(angle.cos() as f32, angle.sin() as f32)
+            })
+            .collect();
+
+        let reversed = (0..size)
+            .map(|i| {
+                if bits == 0 {
+                    0
+                } else {
+                    i.reverse_bits() >> (usize::BITS - bits)
+                }
+            })
+            .collect();
+
+        Self {
+            size,
+            twiddles,
+            reversed,
+        }
+    }
+
+    /// Nombre de passes papillon d'une transformée
+    pub fn passes(&self) -> usize {
+        self.size.trailing_zeros() as usize
+    }
+
+    /// Permutation en ordre bit-inversé, préalable aux passes
+    pub fn bit_reverse(&self, data: &mut [Complex]) {
+        for i in 0..self.size {
+            let j = self.reversed[i];
+            if i < j {
+                data.swap(i, j);
+            }
+        }
+    }
+
+    /// Passe papillon `pass` (de 0 à `passes() - 1`), pour étaler une transformée
+    /// sur plusieurs appels
+    pub fn pass(&self, data: &mut [Complex], pass: usize) {
+        let half = 1 << pass;
+        let stride = self.size / (half * 2);
+        for start in (0..self.size).step_by(half * 2) {
+            for k in 0..half {
+                let t = data[start + k + half] * self.twiddles[k * stride];
+                let u = data[start + k];
+                data[start + k] = u + t;
+                data[start + k + half] = u - t;
+            }
+        }
+    }
+}
@@ -1,6 +1,13 @@
 use std::any::Any;
 
-use crate::utils::{constants::SAMPLE_RATE, types::Mix};
+use web_sys::console;
+
+use crate::utils::{constants::SAMPLE_RATE, toolkit::ToolKit, types::Mix};
+
+/// Cascade maximale d'un filtre : quatre cellules, soit 48 dB/oct
+const MAX_FILTER_STAGES: usize = 4;
+const MIN_FILTER_FREQUENCY: f32 = 10.0;
+const MIN_FILTER_Q: f32 = 0.05;
 
 pub struct MemoryBuffer {
     pub buffer: Vec<f32>,
@@ -9,9 +16,11 @@ pub struct MemoryBuffer {
 }
 
 impl MemoryBuffer {
-    /// Crée un buffer pour `duration_seconds` à `sample_rate` Hz
+    /// Crée un buffer pour `duration_seconds` à `sample_rate` Hz. La taille est un
+    /// nombre entier de paires stéréo, les écritures et lectures allant par paires.
     pub fn new(sample_rate: usize, duration_seconds: f32) -> Self {
-        let size = (sample_rate as f32 * duration_seconds * 2.0) as usize;
+        let frames = ((sample_rate as f32 * duration_seconds) as usize).max(1);
+        let size = frames * 2;
         Self {
             buffer: vec![0.0; size],
             size,
@@ -30,6 +39,21 @@ impl MemoryBuffer {
         (self.buffer[read_index], self.buffer[read_index + 1])
     }
 
+    /// Lecture d'une paire stéréo à un retard fractionnaire, interpolée linéairement,
+    /// 1.0 étant la dernière paire écrite
+    pub fn read_interpolated(&self, delay_samples: f32) -> (f32, f32) {
+        let frames = self.size / 2;
+        let position = ((self.write_index / 2) as f32 - delay_samples).rem_euclid(frames as f32);
+        let index = position.floor() as usize % frames;
+        let next = (index + 1) % frames;
+        let frac = position - position.floor();
+
+        let l = self.buffer[index * 2] + (self.buffer[next * 2] - self.buffer[index * 2]) * frac;
+        let r = self.buffer[index * 2 + 1]
+            + (self.buffer[next * 2 + 1] - self.buffer[index * 2 + 1]) * frac;
+        (l, r)
+    }
+
     pub fn read_left(&self, delay_samples: usize) -> f32 {
         // On recule de delay_samples * 2 cases (car stéréo)
         let read_index = (self.size + self.write_index - delay_samples * 2) % self.size;
@@ -46,6 +70,17 @@ impl MemoryBuffer {
 pub enum EffectsEnum {
     Echo,
     Filter,
+    Reverb,
+    Convolution,
+    Chorus,
+    Flanger,
+    Phaser,
+    Distortion,
+    Bitcrusher,
+    Compressor,
+    Limiter,
+    Gate,
+    Equalizer,
 }
 
 impl TryFrom<u32> for EffectsEnum {
@@ -55,6 +90,17 @@ impl TryFrom<u32> for EffectsEnum {
         match value {
             0 => Ok(EffectsEnum::Echo),
             1 => Ok(EffectsEnum::Filter),
+            2 => Ok(EffectsEnum::Reverb),
+            3 => Ok(EffectsEnum::Convolution),
+            4 => Ok(EffectsEnum::Chorus),
+            5 => Ok(EffectsEnum::Flanger),
+            6 => Ok(EffectsEnum::Phaser),
+            7 => Ok(EffectsEnum::Distortion),
+            8 => Ok(EffectsEnum::Bitcrusher),
+            9 => Ok(EffectsEnum::Compressor),
+            10 => Ok(EffectsEnum::Limiter),
+            11 => Ok(EffectsEnum::Gate),
+            12 => Ok(EffectsEnum::Equalizer),
             _ => Err(()),
         }
     }
@@ -63,9 +109,18 @@ pub trait EffectTrait: Any {
     fn id(&self) -> usize;
     fn process(&mut self, sample_l: &mut f32, sample_r: &mut f32);
 
+    /// Appelé quand le tempo du transport change, pour les effets synchronisés
+    fn set_tempo(&mut self, _bpm: f32) {}
+
+    /// Réduction de gain courante en dB, exposée par les effets de dynamique
+    fn gain_reduction(&self) -> Option<f32> {
+        None
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
+#[derive(Clone, Copy)]
 pub struct BiquadCoeffs {
     pub b0: f32,
     pub b1: f32,
@@ -137,14 +192,231 @@ impl BiquadCoeffs {
             a2: a2 / a0,
         }
     }
+
+    pub fn calc_coeffs_for_bandpass(frequency: f32, q: f32) -> BiquadCoeffs {
+        let w0 = 2.0 * std::f32::consts::PI * frequency / SAMPLE_RATE;
+        let alpha = (w0).sin() / (2.0 * q);
+
+        // Gain unitaire au centre de la bande
+        BiquadCoeffs::normalized(
+            alpha,
+            0.0,
+            -alpha,
+            1.0 + alpha,
+            -2.0 * w0.cos(),
+            1.0 - alpha,
+        )
+    }
+
+    pub fn calc_coeffs_for_notch(frequency: f32, q: f32) -> BiquadCoeffs {
+        let w0 = 2.0 * std::f32::consts::PI * frequency / SAMPLE_RATE;
+        let alpha = (w0).sin() / (2.0 * q);
+        let cos = w0.cos();
+
+        BiquadCoeffs::normalized(1.0, -2.0 * cos, 1.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+    }
+
+    pub fn calc_coeffs_for_allpass(frequency: f32, q: f32) -> BiquadCoeffs {
+        let w0 = 2.0 * std::f32::consts::PI * frequency / SAMPLE_RATE;
+        let alpha = (w0).sin() / (2.0 * q);
+        let cos = w0.cos();
+
+        BiquadCoeffs::normalized(
+            1.0 - alpha,
+            -2.0 * cos,
+            1.0 + alpha,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    pub fn calc_coeffs_for_low_shelf(frequency: f32, q: f32, gain_db: f32) -> BiquadCoeffs {
+        let a = 10f32.powf(gain_db / 40.0);
+        let w0 = 2.0 * std::f32::consts::PI * frequency / SAMPLE_RATE;
+        let alpha = (w0).sin() / (2.0 * q);
+        let cos = w0.cos();
+        let beta = 2.0 * a.sqrt() * alpha;
+
+        BiquadCoeffs::normalized(
+            a * ((a + 1.0) - (a - 1.0) * cos + beta),
+            2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+            a * ((a + 1.0) - (a - 1.0) * cos - beta),
+            (a + 1.0) + (a - 1.0) * cos + beta,
+            -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+            (a + 1.0) + (a - 1.0) * cos - beta,
+        )
+    }
+
+    pub fn calc_coeffs_for_high_shelf(frequency: f32, q: f32, gain_db: f32) -> BiquadCoeffs {
+        let a = 10f32.powf(gain_db / 40.0);
+        let w0 = 2.0 * std::f32::consts::PI * frequency / SAMPLE_RATE;
+        let alpha = (w0).sin() / (2.0 * q);
+        let cos = w0.cos();
+        let beta = 2.0 * a.sqrt() * alpha;
+
+        BiquadCoeffs::normalized(
+            a * ((a + 1.0) + (a - 1.0) * cos + beta),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+            a * ((a + 1.0) + (a - 1.0) * cos - beta),
+            (a + 1.0) - (a - 1.0) * cos + beta,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos),
+            (a + 1.0) - (a - 1.0) * cos - beta,
+        )
+    }
+
+    /// Gain linéaire de la cellule à `frequency`, évalué sur le cercle unité
+    pub fn magnitude(&self, frequency: f32) -> f32 {
+        let w = 2.0 * std::f32::consts::PI * frequency / SAMPLE_RATE;
+        let (cos1, sin1) = (w.cos(), w.sin());
+        let (cos2, sin2) = ((2.0 * w).cos(), (2.0 * w).sin());
+
+        let num_re = self.b0 + self.b1 * cos1 + self.b2 * cos2;
+        let num_im = self.b1 * sin1 + self.b2 * sin2;
+        let den_re = 1.0 + self.a1 * cos1 + self.a2 * cos2;
+        let den_im = self.a1 * sin1 + self.a2 * sin2;
+
+        ((num_re * num_re + num_im * num_im) / (den_re * den_re + den_im * den_im)).sqrt()
+    }
+
+    fn normalized(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> BiquadCoeffs {
+        BiquadCoeffs {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
 }
-pub struct BiquadFilter {
-    id: usize,
+
+#[derive(Clone, Copy)]
+pub enum FilterType {
+    Lowpass,
+    Highpass,
+    Bell,
+    Bandpass,
+    Notch,
+    Allpass,
+    LowShelf,
+    HighShelf,
+    Lowpass24,
+    Highpass24,
+    Lowpass48,
+    Highpass48,
+}
+
+impl TryFrom<u8> for FilterType {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(FilterType::Lowpass),
+            1 => Ok(FilterType::Highpass),
+            2 => Ok(FilterType::Bell),
+            3 => Ok(FilterType::Bandpass),
+            4 => Ok(FilterType::Notch),
+            5 => Ok(FilterType::Allpass),
+            6 => Ok(FilterType::LowShelf),
+            7 => Ok(FilterType::HighShelf),
+            8 => Ok(FilterType::Lowpass24),
+            9 => Ok(FilterType::Highpass24),
+            10 => Ok(FilterType::Lowpass48),
+            11 => Ok(FilterType::Highpass48),
+            _ => Err("Type de filtre inconnu"),
+        }
+    }
+}
+
+impl FilterType {
+    /// Cellules du second ordre en cascade : 12 dB/oct chacune
+    pub fn stage_count(self) -> usize {
+        match self {
+            FilterType::Lowpass24 | FilterType::Highpass24 => 2,
+            FilterType::Lowpass48 | FilterType::Highpass48 => 4,
+            _ => 1,
+        }
+    }
+
+    /// Coefficients de la cellule `index`. Les pentes raides cascadent les cellules
+    /// d'un Butterworth, la résonance `q` ne s'appliquant qu'à la plus sélective
+    pub fn stage_coeffs(self, frequency: f32, q: f32, gain: f32, index: usize) -> BiquadCoeffs {
+        let count = self.stage_count();
+        let stage_q = if count == 1 {
+            q
+        } else {
+            let angle = std::f32::consts::PI * (2 * index + 1) as f32 / (4 * count) as f32;
+            let butterworth = 1.0 / (2.0 * angle.cos());
+            if index == count - 1 {
+                butterworth * q * std::f32::consts::SQRT_2
+            } else {
+                butterworth
+            }
+        };
+
+        match self {
+            FilterType::Lowpass | FilterType::Lowpass24 | FilterType::Lowpass48 => {
+                BiquadCoeffs::calc_coeffs_for_lowpass(frequency, stage_q)
+            }
+            FilterType::Highpass | FilterType::Highpass24 | FilterType::Highpass48 => {
+                BiquadCoeffs::calc_coeffs_for_highpass(frequency, stage_q)
+            }
+            FilterType::Bell => BiquadCoeffs::calc_coeffs_for_bell(frequency, q, gain),
+            FilterType::Bandpass => BiquadCoeffs::calc_coeffs_for_bandpass(frequency, q),
+            FilterType::Notch => BiquadCoeffs::calc_coeffs_for_notch(frequency, q),
+            FilterType::Allpass => BiquadCoeffs::calc_coeffs_for_allpass(frequency, q),
+            FilterType::LowShelf => BiquadCoeffs::calc_coeffs_for_low_shelf(frequency, q, gain),
+            FilterType::HighShelf => BiquadCoeffs::calc_coeffs_for_high_shelf(frequency, q, gain),
+        }
+    }
+}
+
+/// Cellule du second ordre et son état, en forme directe II transposée
+#[derive(Clone, Copy)]
+pub struct BiquadStage {
     pub coeffs: BiquadCoeffs,
     pub z1l: f32,
     pub z1r: f32,
     pub z2l: f32,
     pub z2r: f32,
+}
+
+impl BiquadStage {
+    pub fn new(coeffs: BiquadCoeffs) -> Self {
+        Self {
+            coeffs,
+            z1l: 0.0,
+            z1r: 0.0,
+            z2l: 0.0,
+            z2r: 0.0,
+        }
+    }
+
+    pub fn process(&mut self, input_l: f32, input_r: f32) -> (f32, f32) {
+        let output_l = self.coeffs.b0 * input_l + self.z1l;
+        let output_r = self.coeffs.b0 * input_r + self.z1r;
+
+        self.z1l = self.coeffs.b1 * input_l - self.coeffs.a1 * output_l + self.z2l;
+        self.z1r = self.coeffs.b1 * input_r - self.coeffs.a1 * output_r + self.z2r;
+
+        self.z2l = self.coeffs.b2 * input_l - self.coeffs.a2 * output_l;
+        self.z2r = self.coeffs.b2 * input_r - self.coeffs.a2 * output_r;
+
+        (output_l, output_r)
+    }
+
+    pub fn reset(&mut self) {
+        self.z1l = 0.0;
+        self.z1r = 0.0;
+        self.z2l = 0.0;
+        self.z2r = 0.0;
+    }
+}
+
+pub struct BiquadFilter {
+    id: usize,
+    pub stages: [BiquadStage; MAX_FILTER_STAGES],
+    pub stage_count: usize,
     pub frequency: f32,
     pub q: f32,
     pub filter_type: u8,
@@ -153,55 +425,64 @@ pub struct BiquadFilter {
 
 impl BiquadFilter {
     pub fn new(frequency: f32, q: f32, id: usize, filter_type: u8, gain: f32) -> Self {
-        let coeffs: BiquadCoeffs = match filter_type {
-            0 => BiquadCoeffs::calc_coeffs_for_lowpass(frequency, q),
-            1 => BiquadCoeffs::calc_coeffs_for_highpass(frequency, q),
-            _ => BiquadCoeffs::calc_coeffs_for_bell(frequency, q, gain),
-        };
-        BiquadFilter {
-            coeffs,
+        let mut filter = BiquadFilter {
             id: id,
-            z1l: 0.0,
-            z1r: 0.0,
-            z2l: 0.0,
-            z2r: 0.0,
+            stages: [BiquadStage::new(BiquadCoeffs::calc_coeffs_for_lowpass(frequency, q));
+                MAX_FILTER_STAGES],
+            stage_count: 1,
             frequency,
             q,
             filter_type,
             gain,
-        }
+        };
+        filter.edit(frequency, q, filter_type, gain);
+        filter
     }
 
     pub fn edit(&mut self, frequency: f32, q: f32, filter_type: u8, gain: f32) {
-        self.coeffs = match filter_type {
-            0 => BiquadCoeffs::calc_coeffs_for_lowpass(frequency, q),
-            1 => BiquadCoeffs::calc_coeffs_for_highpass(frequency, q),
-            _ => BiquadCoeffs::calc_coeffs_for_bell(frequency, q, gain),
+        let kind = match FilterType::try_from(filter_type) {
+            Ok(kind) => kind,
+            Err(e) => {
+                console::error_1(&e.into());
+                return;
+            }
         };
+        // Au-delà de Nyquist les coefficients deviennent instables
+        let frequency = frequency.clamp(MIN_FILTER_FREQUENCY, SAMPLE_RATE * 0.49);
+        let q = q.max(MIN_FILTER_Q);
+
+        let stage_count = kind.stage_count();
+        if stage_count != self.stage_count {
+            self.stages.iter_mut().for_each(BiquadStage::reset);
+            self.stage_count = stage_count;
+        }
+        for (index, stage) in self.stages.iter_mut().take(stage_count).enumerate() {
+            stage.coeffs = kind.stage_coeffs(frequency, q, gain, index);
+        }
 
         self.frequency = frequency;
         self.q = q;
         self.filter_type = filter_type;
         self.gain = gain
     }
+
+    pub fn magnitude(&self, frequency: f32) -> f32 {
+        self.stages
+            .iter()
+            .take(self.stage_count)
+            .map(|stage| stage.coeffs.magnitude(frequency))
+            .product()
+    }
 }
 
 impl EffectTrait for BiquadFilter {
     fn id(&self) -> usize {
         self.id
     }
-    fn process(&mut self, input_sample_r: &mut f32, input_sample_l: &mut f32) {
-        let output_sample_r = self.coeffs.b0 * *input_sample_r + self.z1r;
-        let output_sample_l = self.coeffs.b0 * *input_sample_l + self.z1l;
-
-        self.z1l = self.coeffs.b1 * *input_sample_l - self.coeffs.a1 * output_sample_l + self.z2l;
-        self.z1r = self.coeffs.b1 * *input_sample_r - self.coeffs.a1 * output_sample_r + self.z2r;
-
-        self.z2l = self.coeffs.b2 * *input_sample_l - self.coeffs.a2 * output_sample_l;
-        self.z2r = self.coeffs.b2 * *input_sample_r - self.coeffs.a2 * output_sample_r;
-
-        *input_sample_l = output_sample_l;
-        *input_sample_r = output_sample_r;
+    fn process(&mut self, input_sample_l: &mut f32, input_sample_r: &mut f32) {
+        for stage in self.stages.iter_mut().take(self.stage_count) {
+            (*input_sample_l, *input_sample_r) = stage.process(*input_sample_l, *input_sample_r);
+        }
     }
 
     fn as_any_mut(&mut self) -> &mut dyn Any {
@@ -225,6 +506,8 @@ pub struct Echo {
     pub r_delay_offset: usize,
     pub l_delay_offset: usize,
     pub mix: Mix,
+    /// Durée du délai en temps, 0 pour un délai libre en millisecondes
+    pub sync: f32,
 }
 
 impl Echo {
@@ -244,6 +527,7 @@ impl Echo {
             r_delay_offset: r_delay_offset,
             l_delay_offset: l_delay_offset,
             id: id,
+            sync: 0.0,
         }
     }
 }
@@ -260,6 +544,12 @@ impl EffectTrait for Echo {
         self.memory.write(*input_l, *input_r);
     }
 
+    fn set_tempo(&mut self, bpm: f32) {
+        if self.sync > 0.0 {
+            self.delay = ToolKit::convert_ms_to_sample(self.sync * 60_000.0 / bpm);
+        }
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn Any {
         self
     }
@@ -1,10 +1,25 @@
 use std::iter::Filter;
 
+use js_sys::Float32Array;
 use web_sys::console;
 
 use crate::{
-    sound_engine::dsp::fx::{BiquadFilter, Echo, EchoParams, EffectTrait},
-    utils::{toolkit::ToolKit, types::Mix},
+    global::TRANSPORT,
+    sound_engine::dsp::{
+        bitcrusher::Bitcrusher,
+        convolution::Convolution,
+        distortion::Distortion,
+        dynamics::{Compressor, Gate, Limiter},
+        equalizer::Equalizer,
+        fx::{BiquadFilter, Echo, EchoParams, EffectTrait},
+        modulation::{Chorus, Flanger, Phaser},
+        reverb::Reverb,
+    },
+    utils::{
+        constants::{MAX_REPORTED_METERS, REPORT_METERS_INDEX},
+        toolkit::ToolKit,
+        types::Mix,
+    },
 };
 
 pub struct Mixer {
@@ -33,6 +48,12 @@ impl Mixer {
         }
     }
 
+    pub fn set_tempo(&mut self, bpm: f32) {
+        for effect in &mut self.effects {
+            effect.set_tempo(bpm);
+        }
+    }
+
     pub fn create_echo(&mut self, id: u32) {
         let echo = Echo::new(
             self.ECHO_DEFAULT_PRESET.delay,
@@ -56,6 +77,10 @@ impl Mixer {
                     3 => echo.r_delay_offset = ToolKit::convert_ms_to_sample(value),
                     4 => echo.mix.dry = value.min(1.0),
                     5 => echo.mix.wet = value.min(1.0),
+                    6 => {
+                        echo.sync = value.max(0.0);
+                        echo.set_tempo(TRANSPORT.with(|t| t.lock().unwrap().bpm));
+                    }
                     _ => console::error_1(&format!("Cannot update {}", param_index).into()),
                 }
             } else if let Some(filter) = effect.as_any_mut().downcast_mut::<BiquadFilter>() {
@@ -76,10 +101,36 @@ impl Mixer {
                     ),
                     _ => console::error_1(&format!("Cannot update {}", param_index).into()),
                 }
+            } else if let Some(reverb) = effect.as_any_mut().downcast_mut::<Reverb>() {
+                reverb.update(param_index, value);
+            } else if let Some(convolution) = effect.as_any_mut().downcast_mut::<Convolution>() {
+                convolution.update(param_index, value);
+            } else if let Some(chorus) = effect.as_any_mut().downcast_mut::<Chorus>() {
+                chorus.update(param_index, value);
+            } else if let Some(flanger) = effect.as_any_mut().downcast_mut::<Flanger>() {
+                flanger.update(param_index, value);
+            } else if let Some(phaser) = effect.as_any_mut().downcast_mut::<Phaser>() {
+                phaser.update(param_index, value);
+            } else if let Some(distortion) = effect.as_any_mut().downcast_mut::<Distortion>() {
+                distortion.update(param_index, value);
+            } else if let Some(bitcrusher) = effect.as_any_mut().downcast_mut::<Bitcrusher>() {
+                bitcrusher.update(param_index, value);
+            } else if let Some(compressor) = effect.as_any_mut().downcast_mut::<Compressor>() {
+                compressor.update(param_index, value);
+            } else if let Some(limiter) = effect.as_any_mut().downcast_mut::<Limiter>() {
+                limiter.update(param_index, value);
+            } else if let Some(gate) = effect.as_any_mut().downcast_mut::<Gate>() {
+                gate.update(param_index, value);
+            } else if let Some(equalizer) = effect.as_any_mut().downcast_mut::<Equalizer>() {
+                equalizer.update(param_index, value);
             }
         }
     }
 
+    pub fn contains_fx(&self, id: u32) -> bool {
+        self.effects.iter().any(|e| e.id() == id as usize)
+    }
+
     pub fn remove_fx(&mut self, id: u32) {
         self.effects.retain(|e| e.id() != id as usize);
     }
@@ -88,4 +139,84 @@ impl Mixer {
         let filter = BiquadFilter::new(800.0, 0.7, id as usize, 0, 5.0);
         self.effects.push(Box::new(filter));
     }
+
+    pub fn create_reverb(&mut self, id: u32) {
+        self.effects.push(Box::new(Reverb::new(id as usize)));
+    }
+
+    pub fn create_convolution(&mut self, id: u32) {
+        self.effects.push(Box::new(Convolution::new(id as usize)));
+    }
+
+    pub fn create_chorus(&mut self, id: u32) {
+        self.effects.push(Box::new(Chorus::new(id as usize)));
+    }
+
+    pub fn create_flanger(&mut self, id: u32) {
+        self.effects.push(Box::new(Flanger::new(id as usize)));
+    }
+
+    pub fn create_phaser(&mut self, id: u32) {
+        self.effects.push(Box::new(Phaser::new(id as usize)));
+    }
+
+    pub fn create_distortion(&mut self, id: u32) {
+        self.effects.push(Box::new(Distortion::new(id as usize)));
+    }
+
+    pub fn create_bitcrusher(&mut self, id: u32) {
+        self.effects.push(Box::new(Bitcrusher::new(id as usize)));
+    }
+
+    pub fn create_compressor(&mut self, id: u32) {
+        self.effects.push(Box::new(Compressor::new(id as usize)));
+    }
+
+    pub fn create_limiter(&mut self, id: u32) {
+        self.effects.push(Box::new(Limiter::new(id as usize)));
+    }
+
+    pub fn create_gate(&mut self, id: u32) {
+        self.effects.push(Box::new(Gate::new(id as usize)));
+    }
+
+    pub fn create_equalizer(&mut self, id: u32) {
+        self.effects.push(Box::new(Equalizer::new(id as usize)));
+    }
+
+    /// Courbe de réponse d'un égaliseur qui l'a demandée, au plus une par appel
+    pub fn take_eq_curve(&mut self) -> Option<Vec<u8>> {
+        self.effects.iter_mut().find_map(|effect| {
+            effect
+                .as_any_mut()
+                .downcast_mut::<Equalizer>()
+                .and_then(Equalizer::take_curve)
+        })
+    }
+
+    /// Écrit les paires (id, réduction en dB) des effets de dynamique à la suite
+    /// des `count` déjà écrites
+    pub fn write_meters(&self, report: &Float32Array, count: &mut u32) {
+        for effect in &self.effects {
+            let Some(reduction) = effect.gain_reduction() else {
+                continue;
+            };
+            if *count >= MAX_REPORTED_METERS {
+                return;
+            }
+            let index = REPORT_METERS_INDEX + 1 + *count * 2;
+            report.set_index(index, effect.id() as f32);
+            report.set_index(index + 1, reduction);
+            *count += 1;
+        }
+    }
+
+    /// Transmet l'arrivée d'un sample aux convolutions qui l'attendent comme réponse
+    pub fn sample_loaded(&mut self, sample_id: u32) {
+        for effect in &mut self.effects {
+            if let Some(convolution) = effect.as_any_mut().downcast_mut::<Convolution>() {
+                convolution.sample_loaded(sample_id);
+            }
+        }
+    }
 }
@@ -1,2 +1,10 @@
+pub mod bitcrusher;
+pub mod convolution;
+pub mod distortion;
+pub mod dynamics;
+pub mod equalizer;
+pub mod fft;
 pub mod fx;
 pub mod mixer;
+pub mod modulation;
+pub mod reverb;
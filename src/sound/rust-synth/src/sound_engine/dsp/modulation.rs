@@ -0,0 +1,278 @@
+use std::{any::Any, f32::consts::TAU};
+
+use web_sys::console;
+
+use crate::{
+    sound_engine::dsp::fx::{EffectTrait, MemoryBuffer},
+    utils::{constants::SAMPLE_RATE, types::Mix},
+};
+
+const MAX_CHORUS_VOICES: usize = 4;
+const MAX_CHORUS_DELAY_MS: f32 = 30.0;
+const MAX_CHORUS_DEPTH_MS: f32 = 15.0;
+const MAX_FLANGER_DELAY_MS: f32 = 10.0;
+const MAX_FLANGER_DEPTH_MS: f32 = 10.0;
+const MAX_PHASER_STAGES: usize = 12;
+const MAX_LFO_RATE: f32 = 20.0;
+
+fn ms_to_samples(ms: f32) -> f32 {
+    ms / 1000.0 * SAMPLE_RATE
+}
+
+/// LFO sinusoïdal ramené entre 0 et 1, `phase` en cycles
+fn unipolar_lfo(phase: f32) -> f32 {
+    0.5 + 0.5 * (phase * TAU).sin()
+}
+
+/// Chorus : plusieurs lectures du signal retardé, chacune modulée par un LFO décalé
+pub struct Chorus {
+    id: usize,
+    /// Fréquence du LFO en Hz
+    pub rate: f32,
+    /// Excursion du retard, en ms
+    pub depth: f32,
+    /// Retard de base, en ms
+    pub delay: f32,
+    pub voices: usize,
+    /// Décalage de phase entre la gauche et la droite, entre 0 et 1
+    pub spread: f32,
+    pub mix: Mix,
+
+    memory: MemoryBuffer,
+    phase: f32,
+}
+
+impl Chorus {
+    pub fn new(id: usize) -> Self {
+        let duration = (MAX_CHORUS_DELAY_MS + MAX_CHORUS_DEPTH_MS) / 1000.0 + 0.01;
+        Self {
+            id,
+            rate: 0.8,
+            depth: 3.0,
+            delay: 12.0,
+            voices: 3,
+            spread: 0.5,
+            mix: Mix { dry: 1.0, wet: 0.5 },
+            memory: MemoryBuffer::new(SAMPLE_RATE as usize, duration),
+            phase: 0.0,
+        }
+    }
+
+    /// Paramètres : 0 vitesse (Hz), 1 profondeur (ms), 2 retard (ms), 3 voix,
+    /// 4 écart stéréo, 5 dry, 6 wet
+    pub fn update(&mut self, param: u32, value: f32) {
+        match param {
+            0 => self.rate = value.clamp(0.0, MAX_LFO_RATE),
+            1 => self.depth = value.clamp(0.0, MAX_CHORUS_DEPTH_MS),
+            2 => self.delay = value.clamp(1.0, MAX_CHORUS_DELAY_MS),
+            3 => self.voices = (value as usize).clamp(1, MAX_CHORUS_VOICES),
+            4 => self.spread = value.clamp(0.0, 1.0),
+            5 => self.mix.dry = value.min(1.0),
+            6 => self.mix.wet = value.min(1.0),
+            _ => console::error_1(&format!("Cannot update chorus {}", param).into()),
+        }
+    }
+}
+
+impl EffectTrait for Chorus {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn process(&mut self, sample_l: &mut f32, sample_r: &mut f32) {
+        self.phase = (self.phase + self.rate / SAMPLE_RATE) % 1.0;
+
+        let mut wet_l = 0.0;
+        let mut wet_r = 0.0;
+        for voice in 0..self.voices {
+            let phase = self.phase + voice as f32 / self.voices as f32;
+            let delay_l = self.delay + self.depth * unipolar_lfo(phase);
+            let delay_r = self.delay + self.depth * unipolar_lfo(phase + self.spread * 0.5);
+
+            wet_l += self.memory.read_interpolated(ms_to_samples(delay_l)).0;
+            wet_r += self.memory.read_interpolated(ms_to_samples(delay_r)).1;
+        }
+
+        self.memory.write(*sample_l, *sample_r);
+
+        let gain = 1.0 / self.voices as f32;
+        *sample_l = self.mix.dry * *sample_l + self.mix.wet * wet_l * gain;
+        *sample_r = self.mix.dry * *sample_r + self.mix.wet * wet_r * gain;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Flanger : retard court modulé, réinjecté pour creuser le filtre en peigne
+pub struct Flanger {
+    id: usize,
+    pub rate: f32,
+    pub depth: f32,
+    pub delay: f32,
+    /// Réinjection, négative pour inverser les creux et les pics du peigne
+    pub feedback: f32,
+    pub mix: Mix,
+
+    memory: MemoryBuffer,
+    phase: f32,
+}
+
+impl Flanger {
+    pub fn new(id: usize) -> Self {
+        let duration = (MAX_FLANGER_DELAY_MS + MAX_FLANGER_DEPTH_MS) / 1000.0 + 0.01;
+        Self {
+            id,
+            rate: 0.25,
+            depth: 2.0,
+            delay: 1.0,
+            feedback: 0.5,
+            mix: Mix { dry: 1.0, wet: 0.7 },
+            memory: MemoryBuffer::new(SAMPLE_RATE as usize, duration),
+            phase: 0.0,
+        }
+    }
+
+    /// Paramètres : 0 vitesse (Hz), 1 profondeur (ms), 2 retard (ms), 3 réinjection,
+    /// 4 dry, 5 wet
+    pub fn update(&mut self, param: u32, value: f32) {
+        match param {
+            0 => self.rate = value.clamp(0.0, MAX_LFO_RATE),
+            1 => self.depth = value.clamp(0.0, MAX_FLANGER_DEPTH_MS),
+            2 => self.delay = value.clamp(0.1, MAX_FLANGER_DELAY_MS),
+            3 => self.feedback = value.clamp(-0.95, 0.95),
+            4 => self.mix.dry = value.min(1.0),
+            5 => self.mix.wet = value.min(1.0),
+            _ => console::error_1(&format!("Cannot update flanger {}", param).into()),
+        }
+    }
+}
+
+impl EffectTrait for Flanger {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn process(&mut self, sample_l: &mut f32, sample_r: &mut f32) {
+        self.phase = (self.phase + self.rate / SAMPLE_RATE) % 1.0;
+
+        // Droite en quadrature pour élargir l'image stéréo
+        let delay_l = self.delay + self.depth * unipolar_lfo(self.phase);
+        let delay_r = self.delay + self.depth * unipolar_lfo(self.phase + 0.25);
+        let wet_l = self.memory.read_interpolated(ms_to_samples(delay_l)).0;
+        let wet_r = self.memory.read_interpolated(ms_to_samples(delay_r)).1;
+
+        self.memory.write(
+            *sample_l + wet_l * self.feedback,
+            *sample_r + wet_r * self.feedback,
+        );
+
+        *sample_l = self.mix.dry * *sample_l + self.mix.wet * wet_l;
+        *sample_r = self.mix.dry * *sample_r + self.mix.wet * wet_r;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Phaser : cascade de passe-tout du premier ordre dont la fréquence de coupure
+/// est balayée par un LFO, les creux apparaissant en mélangeant avec le signal sec
+pub struct Phaser {
+    id: usize,
+    pub rate: f32,
+    /// Balayage autour de la fréquence centrale, 1 pour deux octaves de part et d'autre
+    pub depth: f32,
+    /// Fréquence centrale en Hz
+    pub frequency: f32,
+    pub stages: usize,
+    pub feedback: f32,
+    pub mix: Mix,
+
+    states: [[f32; MAX_PHASER_STAGES]; 2],
+    last_outputs: [f32; 2],
+    phase: f32,
+}
+
+impl Phaser {
+    pub fn new(id: usize) -> Self {
+        Self {
+            id,
+            rate: 0.5,
+            depth: 0.7,
+            frequency: 800.0,
+            stages: 4,
+            feedback: 0.3,
+            mix: Mix { dry: 1.0, wet: 0.7 },
+            states: [[0.0; MAX_PHASER_STAGES]; 2],
+            last_outputs: [0.0; 2],
+            phase: 0.0,
+        }
+    }
+
+    /// Paramètres : 0 vitesse (Hz), 1 profondeur, 2 fréquence centrale (Hz),
+    /// 3 nombre d'étages (pair), 4 réinjection, 5 dry, 6 wet
+    pub fn update(&mut self, param: u32, value: f32) {
+        match param {
+            0 => self.rate = value.clamp(0.0, MAX_LFO_RATE),
+            1 => self.depth = value.clamp(0.0, 1.0),
+            2 => self.frequency = value.clamp(20.0, SAMPLE_RATE * 0.45),
+            3 => self.stages = ((value as usize) & !1).clamp(2, MAX_PHASER_STAGES),
+            4 => self.feedback = value.clamp(-0.95, 0.95),
+            5 => self.mix.dry = value.min(1.0),
+            6 => self.mix.wet = value.min(1.0),
+            _ => console::error_1(&format!("Cannot update phaser {}", param).into()),
+        }
+    }
+}
+
+impl EffectTrait for Phaser {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn process(&mut self, sample_l: &mut f32, sample_r: &mut f32) {
+        self.phase = (self.phase + self.rate / SAMPLE_RATE) % 1.0;
+
+        for (channel, sample) in [sample_l, sample_r].into_iter().enumerate() {
+            let lfo = (((self.phase + channel as f32 * 0.25) * TAU).sin()) * self.depth;
+            let frequency = (self.frequency * 2f32.powf(2.0 * lfo)).clamp(20.0, SAMPLE_RATE * 0.45);
+            let t = (std::f32::consts::PI * frequency / SAMPLE_RATE).tan();
+            let a = (t - 1.0) / (t + 1.0);
+
+            let mut x = *sample + self.last_outputs[channel] * self.feedback;
+            for state in self.states[channel].iter_mut().take(self.stages) {
+                let y = a * x + *state;
+                *state = x - a * y;
+                x = y;
+            }
+            self.last_outputs[channel] = x;
+
+            *sample = self.mix.dry * *sample + self.mix.wet * x;
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chorus_runs_for_several_seconds() {
+        let mut chorus = Chorus::new(0);
+        chorus.update(1, MAX_CHORUS_DEPTH_MS);
+        chorus.update(2, MAX_CHORUS_DELAY_MS);
+
+        for n in 0..(3.0 * SAMPLE_RATE) as usize {
+            let input = (n as f32 * 0.01).sin();
+            let (mut left, mut right) = (input, -input);
+            chorus.process(&mut left, &mut right);
+            assert!(left.is_finite() && right.is_finite());
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use std::any::Any;
+
+use web_sys::console;
+
+use crate::{
+    sound_engine::dsp::fx::EffectTrait,
+    utils::{constants::SAMPLE_RATE, toolkit::ToolKit, types::Mix},
+};
+
+const FDN_SIZE: usize = 8;
+/// Longueurs de base des lignes du réseau, en échantillons, premières entre elles
+const FDN_DELAYS: [usize; FDN_SIZE] = [1433, 1601, 1867, 2053, 2251, 2399, 2617, 2797];
+/// Passe-tout de diffusion en entrée, par canal
+const DIFFUSER_DELAYS: [[usize; 4]; 2] = [[142, 107, 379, 277], [151, 113, 367, 293]];
+const MIN_SIZE: f32 = 0.25;
+const MAX_SIZE: f32 = 2.0;
+const MAX_PRE_DELAY_MS: f32 = 500.0;
+/// Excursion maximale de la modulation des lignes, en échantillons
+const MAX_MODULATION_DEPTH: f32 = 16.0;
+const MODULATION_RATE: f32 = 0.5;
+
+/// Ligne à retard circulaire avec lecture fractionnaire
+struct DelayLine {
+    buffer: Vec<f32>,
+    write_index: usize,
+}
+
+impl DelayLine {
+    fn new(length: usize) -> Self {
+        Self {
+            buffer: vec![0.0; length.max(2)],
+            write_index: 0,
+        }
+    }
+
+    /// `delay` en échantillons, 1.0 étant le dernier échantillon écrit
+    fn read(&self, delay: f32) -> f32 {
+        let length = self.buffer.len();
+        let position = (self.write_index as f32 - delay).rem_euclid(length as f32);
+        let index = position.floor() as usize % length;
+        let frac = position - position.floor();
+
+        let a = self.buffer[index];
+        let b = self.buffer[(index + 1) % length];
+        a + (b - a) * frac
+    }
+
+    fn write(&mut self, value: f32) {
+        self.buffer[self.write_index] = value;
+        self.write_index = (self.write_index + 1) % self.buffer.len();
+    }
+}
+
+/// Passe-tout de Schroeder, qui épaissit les transitoires sans colorer le spectre
+struct Allpass {
+    line: DelayLine,
+    delay: f32,
+}
+
+impl Allpass {
+    fn new(delay: usize) -> Self {
+        Self {
+            line: DelayLine::new(delay + 1),
+            delay: delay as f32,
+        }
+    }
+
+    fn process(&mut self, input: f32, gain: f32) -> f32 {
+        let delayed = self.line.read(self.delay);
+        let v = input + gain * delayed;
+        self.line.write(v);
+        delayed - gain * v
+    }
+}
+
+/// Réverbération algorithmique : pré-délai, diffusion par passe-tout puis réseau
+/// de 8 lignes à retard rebouclées par une matrice de Hadamard (FDN), amorties
+/// et légèrement modulées pour éviter les résonances métalliques.
+pub struct Reverb {
+    id: usize,
+    /// Facteur appliqué aux longueurs des lignes, entre 0 et 1
+    pub size: f32,
+    /// Temps de décroissance de 60 dB, en secondes
+    pub decay: f32,
+    pub pre_delay: usize,
+    /// Amortissement des aigus dans la boucle, entre 0 et 1
+    pub damping: f32,
+    /// Gain des passe-tout d'entrée, entre 0 et 1
+    pub diffusion: f32,
+    /// Profondeur de modulation des lignes, entre 0 et 1
+    pub modulation: f32,
+    pub mix: Mix,
+
+    pre_delay_lines: [DelayLine; 2],
+    diffusers: [Vec<Allpass>; 2],
+    lines: Vec<DelayLine>,
+    lengths: [f32; FDN_SIZE],
+    gains: [f32; FDN_SIZE],
+    damping_states: [f32; FDN_SIZE],
+    modulation_phase: f32,
+}
+
+impl Reverb {
+    pub fn new(id: usize) -> Self {
+        let max_pre_delay = ToolKit::convert_ms_to_sample(MAX_PRE_DELAY_MS) + 1;
+        let lines = FDN_DELAYS
+            .iter()
+            .map(|&d| DelayLine::new((d as f32 * MAX_SIZE + MAX_MODULATION_DEPTH) as usize + 2))
+            .collect();
+        let diffusers = DIFFUSER_DELAYS.map(|delays| delays.map(Allpass::new).into());
+
+        let mut reverb = Self {
+            id,
+            size: 0.5,
+            decay: 2.0,
+            pre_delay: ToolKit::convert_ms_to_sample(20.0),
+            damping: 0.4,
+            diffusion: 0.7,
+            modulation: 0.3,
+            mix: Mix { dry: 1.0, wet: 0.3 },
+            pre_delay_lines: [DelayLine::new(max_pre_delay), DelayLine::new(max_pre_delay)],
+            diffusers,
+            lines,
+            lengths: [0.0; FDN_SIZE],
+            gains: [0.0; FDN_SIZE],
+            damping_states: [0.0; FDN_SIZE],
+            modulation_phase: 0.0,
+        };
+        reverb.update_lines();
+        reverb
+    }
+
+    /// Recalcule les longueurs et les gains de boucle pour obtenir le temps de décroissance
+    fn update_lines(&mut self) {
+        let scale = MIN_SIZE + (MAX_SIZE - MIN_SIZE) * self.size;
+
+        for (i, &base) in FDN_DELAYS.iter().enumerate() {
+            self.lengths[i] = base as f32 * scale;
+            // -60 dB après `decay` secondes : g = 10^(-3 * longueur / (rt60 * fe))
+            let rt60 = self.decay.max(0.05) * SAMPLE_RATE;
+            self.gains[i] = 10f32.powf(-3.0 * self.lengths[i] / rt60);
+        }
+    }
+
+    /// Paramètres : 0 taille, 1 décroissance (s), 2 pré-délai (ms), 3 amortissement,
+    /// 4 diffusion, 5 modulation, 6 dry, 7 wet
+    pub fn update(&mut self, param: u32, value: f32) {
+        match param {
+            0 => {
+                self.size = value.clamp(0.0, 1.0);
+                self.update_lines();
+            }
+            1 => {
+                self.decay = value.clamp(0.05, 60.0);
+                self.update_lines();
+            }
+            2 => self.pre_delay = ToolKit::convert_ms_to_sample(value.clamp(0.0, MAX_PRE_DELAY_MS)),
+            3 => self.damping = value.clamp(0.0, 0.99),
+            4 => self.diffusion = value.clamp(0.0, 0.9),
+            5 => self.modulation = value.clamp(0.0, 1.0),
+            6 => self.mix.dry = value.min(1.0),
+            7 => self.mix.wet = value.min(1.0),
+            _ => console::error_1(&format!("Cannot update reverb {}", param).into()),
+        }
+    }
+
+    /// Matrice de Hadamard normalisée, appliquée par papillons
+    fn hadamard(values: &mut [f32; FDN_SIZE]) {
+        let mut h = 1;
+        while h < FDN_SIZE {
+            for i in (0..FDN_SIZE).step_by(h * 2) {
+                for j in i..i + h {
+                    let a = values[j];
+                    let b = values[j + h];
+                    values[j] = a + b;
+                    values[j + h] = a - b;
+                }
+            }
+            h *= 2;
+        }
+
+        let normalization = 1.0 / (FDN_SIZE as f32).sqrt();
+        for value in values.iter_mut() {
+            *value *= normalization;
+        }
+    }
+}
+
+impl EffectTrait for Reverb {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn process(&mut self, sample_l: &mut f32, sample_r: &mut f32) {
+        let pre_delay = self.pre_delay.max(1) as f32;
+        let mut inputs = [*sample_l, *sample_r];
+
+        for (channel, input) in inputs.iter_mut().enumerate() {
+            self.pre_delay_lines[channel].write(*input);
+            *input = self.pre_delay_lines[channel].read(pre_delay);
+
+            for allpass in self.diffusers[channel].iter_mut() {
+                *input = allpass.process(*input, self.diffusion);
+            }
+        }
+
+        self.modulation_phase = (self.modulation_phase + MODULATION_RATE / SAMPLE_RATE) % 1.0;
+        let depth = self.modulation * MAX_MODULATION_DEPTH;
+
+        let mut outputs = [0.0; FDN_SIZE];
+        for (i, output) in outputs.iter_mut().enumerate() {
+            let phase = self.modulation_phase + i as f32 / FDN_SIZE as f32;
+            let offset = depth * (1.0 + (phase * std::f32::consts::TAU).sin()) * 0.5;
+            let delayed = self.lines[i].read(self.lengths[i] + offset);
+
+            // Passe-bas à un pôle dans la boucle : les aigus s'éteignent plus vite
+            self.damping_states[i] =
+                delayed * (1.0 - self.damping) + self.damping_states[i] * self.damping;
+            *output = self.damping_states[i] * self.gains[i];
+        }
+
+        let wet_l = outputs.iter().step_by(2).sum::<f32>() * 0.5;
+        let wet_r = outputs.iter().skip(1).step_by(2).sum::<f32>() * 0.5;
+
+        Reverb::hadamard(&mut outputs);
+        for (i, feedback) in outputs.iter().enumerate() {
+            self.lines[i].write(feedback + inputs[i % 2]);
+        }
+
+        *sample_l = self.mix.dry * *sample_l + self.mix.wet * wet_l;
+        *sample_r = self.mix.dry * *sample_r + self.mix.wet * wet_r;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
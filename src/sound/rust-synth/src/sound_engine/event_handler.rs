@@ -4,54 +4,288 @@ use js_sys::{Atomics, Float32Array, Int32Array};
 use web_sys::console;
 
 use crate::{
-    global::{MIXER, SAMPLE_MANAGER, SHARED_BUFFERS},
-    shared_memory::shared_buffers::{FxBuffers, MidiBuffers, SamplerBuffers},
+    global::{MIXER, SAMPLE_MANAGER, SHARED_BUFFERS, TRANSPORT, TUNING},
+    shared_memory::shared_buffers::{
+        ControlBuffers, FileBuffers, FxBuffers, MidiBuffers, SamplerBuffers,
+    },
     sound_engine::{
         dsp::fx::EffectsEnum,
-        synthetizer::{
-            note_manager::{self, NoteManager},
-            sampler::Sampler,
+        dsp::mixer::Mixer,
+        midi::{
+            cc_map::{CcMap, ControllerKind, MappingTarget},
+            controllers::{ControllerDecoder, ControllerEvent},
+            mpe::{ChannelRole, MpeConfig},
+            recorder::MidiRecorder,
+            smf::{MidiFile, SmfEventKind},
+        },
+        midi_clock::MidiClock,
+        sequencing::{
+            arpeggiator::Arpeggiator, smf_player::SmfPlayer, step_sequencer::StepSequencer,
         },
+        synthetizer::{expression::ExpressionKind, part::Part, sampler::Sampler},
+        tuning::scala::{KeyboardMapping, ScalaScale},
     },
     utils::{
         constants::{
-            FX_EVENT_SIZE_FLOAT, FX_EVENT_SIZE_INT, FX_QUEUE_CAPACITY, OSC_QUEUE_CAPACITY,
+            FX_EVENT_SIZE_FLOAT, FX_EVENT_SIZE_INT, FX_QUEUE_CAPACITY, MAX_PARTS,
+            OSC_QUEUE_CAPACITY, REPORT_METERS_INDEX,
+        },
+        types::{
+            ControlEventDto, ControlTarget, EventType, FileEvent, FileKind, NoteDTO, SampleEvent,
         },
-        toolkit::ToolKit,
-        types::SampleEvent,
     },
 };
 
 pub struct EventHandler {
-    note_manager: Rc<RefCell<NoteManager>>,
-    samplers: Rc<RefCell<Vec<Sampler>>>,
+    parts: Rc<RefCell<Vec<Part>>>,
+    arpeggiator: Rc<RefCell<Arpeggiator>>,
+    sequencer: Rc<RefCell<StepSequencer>>,
+    smf_player: Rc<RefCell<SmfPlayer>>,
+    pub midi_clock: MidiClock,
+    mpe: MpeConfig,
+    controllers: ControllerDecoder,
+    cc_map: CcMap,
+    recorder: MidiRecorder,
+    /// Horloge du processeur au début du traitement des événements du bloc
+    sample_index: u64,
     last_sample_event: SampleEvent,
+    last_file_event: FileEvent,
 }
 
 impl EventHandler {
     pub fn new(
-        note_manager: Rc<RefCell<NoteManager>>,
-        samplers: Rc<RefCell<Vec<Sampler>>>,
+        parts: Rc<RefCell<Vec<Part>>>,
+        arpeggiator: Rc<RefCell<Arpeggiator>>,
+        sequencer: Rc<RefCell<StepSequencer>>,
+        smf_player: Rc<RefCell<SmfPlayer>>,
     ) -> Self {
         Self {
-            note_manager,
-            samplers,
+            parts,
+            arpeggiator,
+            sequencer,
+            smf_player,
+            midi_clock: MidiClock::new(),
+            mpe: MpeConfig::new(),
+            controllers: ControllerDecoder::new(),
+            cc_map: CcMap::new(),
+            recorder: MidiRecorder::new(),
+            sample_index: 0,
             last_sample_event: SampleEvent::default(),
+            last_file_event: FileEvent::default(),
         }
     }
 
-    pub fn process_midi_events(&mut self, midi: &MidiBuffers) -> u32 {
-        midi.process_all_events(|dto| {
-            if dto.velocity > 0 {
-                self.note_manager
-                    .borrow_mut()
-                    .add_note(dto, &self.samplers.borrow_mut());
-            } else {
-                self.note_manager.borrow_mut().end_note(dto);
+    /// `sample_index` date les événements reçus (horloge, enregistrement)
+    pub fn process_midi_events(&mut self, midi: &MidiBuffers, sample_index: u64) -> u32 {
+        self.sample_index = sample_index;
+
+        midi.process_all_events(|event| {
+            let mut dto = event.note;
+            let channel = dto.channel;
+
+            match EventType::try_from(event.event_type) {
+                Ok(EventType::NoteOn) if dto.velocity > 0 => {
+                    let kind = SmfEventKind::NoteOn {
+                        channel,
+                        note: dto.value,
+                        velocity: dto.velocity,
+                    };
+                    self.recorder.record(sample_index, kind);
+                    self.process_note_event(&dto)
+                }
+                Ok(EventType::NoteOn | EventType::NoteOff) => {
+                    dto.velocity = 0;
+                    let kind = SmfEventKind::NoteOff {
+                        channel,
+                        note: dto.value,
+                    };
+                    self.recorder.record(sample_index, kind);
+                    self.process_note_event(&dto)
+                }
+                Ok(EventType::ControlChange) => {
+                    let kind = SmfEventKind::ControlChange {
+                        channel,
+                        controller: dto.value,
+                        value: dto.velocity,
+                    };
+                    self.recorder.record(sample_index, kind);
+
+                    if let Some(event) = self.controllers.decode(channel, dto.value, dto.velocity) {
+                        self.process_controller_event(channel, event);
+                    }
+                }
+                Ok(EventType::PitchBend) => {
+                    // Valeur sur 14 bits centrée sur 8192, ramenée entre -1 et 1
+                    let bend = ((dto.velocity as u32) << 7) | dto.value as u32;
+                    let bend = (bend as f32 - 8192.0) / 8192.0;
+                    self.process_expression(channel, ExpressionKind::PitchBend, bend);
+                }
+                Ok(EventType::ChannelPressure) => {
+                    let pressure = dto.value as f32 / 127.0;
+                    self.process_expression(channel, ExpressionKind::Pressure, pressure);
+                }
+                Ok(EventType::TransportStart) => {
+                    // Le Start MIDI repart du début du morceau
+                    TRANSPORT.with(|t| t.lock().unwrap().beats = 0.0);
+                    self.set_transport_playing(true)
+                }
+                Ok(EventType::TransportContinue) => self.set_transport_playing(true),
+                Ok(EventType::TransportStop) => self.set_transport_playing(false),
+                Ok(EventType::Clock) => self.midi_clock.receive_tick(sample_index),
+                Ok(EventType::SongPosition) => {
+                    // Position sur 14 bits, en doubles croches
+                    let position = ((dto.velocity as u32) << 7) | dto.value as u32;
+                    TRANSPORT.with(|t| t.lock().unwrap().beats = position as f64 / 4.0);
+                }
+                Err(e) => console::error_1(&e.into()),
             }
         })
     }
 
+    /// Le séquenceur suit la lecture du transport
+    fn set_transport_playing(&mut self, playing: bool) {
+        let beats = TRANSPORT.with(|t| {
+            let mut transport = t.lock().unwrap();
+            transport.playing = playing;
+            transport.beats
+        });
+
+        if playing {
+            self.sequencer.borrow_mut().start(beats);
+        } else {
+            self.sequencer.borrow_mut().stop();
+        }
+    }
+
+    fn process_note_event(&mut self, dto: &NoteDTO) {
+        let mut arpeggiator = self.arpeggiator.borrow_mut();
+
+        if arpeggiator.enabled {
+            if dto.velocity > 0 {
+                arpeggiator.note_on(dto);
+                return;
+            }
+            // Une touche enfoncée avant l'activation doit encore être relâchée
+            if arpeggiator.note_off(dto) {
+                return;
+            }
+        }
+
+        Part::dispatch(&mut self.parts.borrow_mut(), dto);
+    }
+
+    /// Le pitch bend arrive entre -1 et 1 et est converti en demi-tons selon la zone MPE.
+    /// Un message du canal maître s'applique à toutes les notes de sa zone.
+    fn process_expression(&mut self, channel: u8, kind: ExpressionKind, value: f32) {
+        let (bend_range, master, members) = match self.mpe.role(channel) {
+            ChannelRole::Master {
+                members,
+                bend_range,
+            } => (bend_range, true, Some(members)),
+            ChannelRole::Member { bend_range } => (bend_range, false, None),
+            ChannelRole::Channel { bend_range } => (bend_range, false, None),
+        };
+
+        let value = match kind {
+            ExpressionKind::PitchBend => value * bend_range,
+            _ => value,
+        };
+
+        let mut parts = self.parts.borrow_mut();
+        for channel in std::iter::once(channel).chain(members.into_iter().flatten()) {
+            Part::set_expression(&mut parts, channel, kind, value, master);
+        }
+    }
+
+    fn process_controller_event(&mut self, channel: u8, event: ControllerEvent) {
+        match event {
+            ControllerEvent::Control { number, value } => {
+                self.process_controller(
+                    channel,
+                    ControllerKind::ControlChange,
+                    number as u16,
+                    value,
+                );
+
+                if number == 74 {
+                    self.process_expression(channel, ExpressionKind::Timbre, value);
+                }
+            }
+            ControllerEvent::Nrpn { number, value } => {
+                self.process_controller(channel, ControllerKind::Nrpn, number, value)
+            }
+            ControllerEvent::Rpn { number, value } => self.process_rpn(channel, number, value),
+        }
+    }
+
+    /// RPN standards : amplitude du pitch bend, accord fin et grossier, configuration MPE
+    fn process_rpn(&mut self, channel: u8, number: u16, value: u16) {
+        let msb = (value >> 7) as f32;
+        let lsb = (value & 0x7F) as f32;
+
+        match number {
+            // Demi-tons en MSB, cents en LSB
+            0 => self.mpe.set_bend_range(channel, msb + lsb / 100.0),
+            // Centré sur 8192, ±100 cents, propre au canal
+            1 => {
+                let semitones = (value as f32 - 8192.0) / 8192.0;
+                self.set_channel_tuning(channel, ExpressionKind::FineTuning, semitones);
+            }
+            // Centré sur 64, en demi-tons, propre au canal
+            2 => self.set_channel_tuning(channel, ExpressionKind::CoarseTuning, msb - 64.0),
+            // Message de configuration MPE, sur le canal maître de la zone
+            6 if channel == 0 => self.mpe.update(0, msb),
+            6 if channel == 15 => self.mpe.update(1, msb),
+            _ => {}
+        }
+    }
+
+    fn set_channel_tuning(&mut self, channel: u8, kind: ExpressionKind, semitones: f32) {
+        Part::set_expression(
+            &mut self.parts.borrow_mut(),
+            channel,
+            kind,
+            semitones,
+            false,
+        );
+    }
+
+    /// Applique les affectations du contrôleur, `value` normalisée entre 0 et 1
+    fn process_controller(&mut self, channel: u8, kind: ControllerKind, number: u16, value: f32) {
+        let parts = &self.parts;
+
+        self.cc_map.process(
+            channel,
+            kind,
+            number,
+            value,
+            |mapping, value| match mapping.target {
+                MappingTarget::Sampler => {
+                    let id = mapping.target_id as u8;
+                    if let Some(sampler) = Part::find_sampler_mut(&mut parts.borrow_mut(), id) {
+                        sampler.update_param(mapping.target_param as u8, value);
+                    }
+                }
+                MappingTarget::Fx => {
+                    EventHandler::with_fx_mixer(parts, mapping.target_id, |mixer| {
+                        mixer.update_fx(mapping.target_id, mapping.target_param, value)
+                    });
+                }
+            },
+        );
+    }
+
+    pub fn write_report(&self, report: &Float32Array) {
+        self.cc_map.write_report(report);
+
+        let mut meters = 0;
+        MIXER.with(|m| m.lock().unwrap().write_meters(report, &mut meters));
+        for part in self.parts.borrow().iter() {
+            part.mixer.write_meters(report, &mut meters);
+        }
+        report.set_index(REPORT_METERS_INDEX, meters as f32);
+    }
+
     pub fn process_osc_events(&mut self, osc_buffers: &SamplerBuffers) {
         let mut read_pos = Atomics::load(&osc_buffers.read_idx, 0).unwrap() as u32;
         let write_pos = Atomics::load(&osc_buffers.write_idx, 0).unwrap() as u32;
@@ -76,59 +310,24 @@ impl EventHandler {
 
             match event_type {
                 0 => {
-                    // add
-                    self.samplers.borrow_mut().push(Sampler {
-                        id: osc_index,
-                        sample_id: 0,
-                        attack_length: ToolKit::convert_ms_to_sample(0.0) as u64,
-                        decay_length: ToolKit::convert_ms_to_sample(10.0) as u64,
-                        sustain_gain: 0.5,
-                        release_length: ToolKit::convert_ms_to_sample(500.0) as u64,
-                        frequency_shift: 1.0,
-                        delay_length: ToolKit::convert_ms_to_sample(0.0) as u64,
-                        phase_shift: 0.0,
-                        gain: 0.5,
-                        gain_l: 1.0,
-                        gain_r: 1.0,
-                    });
+                    // add, la valeur désigne la partie qui reçoit le sampler
+                    match self.parts.borrow_mut().get_mut(value as usize) {
+                        Some(part) => part.samplers.push(Sampler::new(osc_index)),
+                        None => console::error_1(&format!("Cannot find part {}", value).into()),
+                    }
                 }
                 1 => {
                     // remove
-                    if let Some(pos) = self
-                        .samplers
-                        .borrow_mut()
-                        .iter()
-                        .position(|osc| osc.id == osc_index)
-                    {
-                        self.samplers.borrow_mut().remove(pos);
-                    } else {
+                    for part in self.parts.borrow_mut().iter_mut() {
+                        part.samplers.retain(|osc| osc.id != osc_index);
                     }
                 }
                 2 => {
                     // update
-                    if let Some(osc) = self
-                        .samplers
-                        .borrow_mut()
-                        .iter_mut()
-                        .find(|o| o.id == osc_index)
+                    if let Some(osc) =
+                        Part::find_sampler_mut(&mut self.parts.borrow_mut(), osc_index)
                     {
-                        match key {
-                            1 => osc.attack_length = value as u64,
-                            2 => osc.release_length = value as u64,
-                            3 => osc.decay_length = value as u64,
-                            4 => osc.sustain_gain = value * 0.1,
-                            5 => osc.gain = value * 0.1,
-                            6 => osc.delay_length = value as u64,
-                            7 => osc.frequency_shift = value,
-                            8 => osc.phase_shift = value,
-                            9 => osc.sample_id = value as u32,
-                            10 => {
-                                osc.gain_l = (1.0 - value) / 2.0;
-                                osc.gain_r = (1.0 + value) / 2.0
-                            }
-
-                            _ => {}
-                        }
+                        osc.update_param(key, value);
                     }
                 }
                 _ => {}
@@ -160,7 +359,7 @@ impl EventHandler {
             let value = fx_buffer.queue_float.get_index(float_offset);
 
             match event_type {
-                0 => self.add_fx(fx_id, param_index),
+                0 => self.add_fx(fx_id, param_index, value as usize),
                 1 => self.remove_fx(fx_id),
                 2 => self.edit_fx(fx_id, param_index, value),
                 _ => {}
@@ -172,33 +371,59 @@ impl EventHandler {
         Atomics::store(&fx_buffer.read_idx, 0, read_pos as i32).unwrap();
     }
 
-    pub fn add_fx(&mut self, fx_id: u32, param_index: u32) {
+    /// `bus` vaut 0 pour le bus maître, `n` pour la chaîne de la partie `n - 1`
+    pub fn add_fx(&mut self, fx_id: u32, param_index: u32, bus: usize) {
         let effect = EffectsEnum::try_from(param_index).unwrap();
 
-        match effect {
-            EffectsEnum::Echo => MIXER.with(|m| {
-                let mut mixer = m.lock().unwrap();
-                mixer.create_echo(fx_id);
-            }),
-            EffectsEnum::Filter => MIXER.with(|m| {
-                let mut mixer = m.lock().unwrap();
-                mixer.create_filter(fx_id);
-            }),
-        }
+        self.with_bus_mixer(bus, |mixer| match effect {
+            EffectsEnum::Echo => mixer.create_echo(fx_id),
+            EffectsEnum::Filter => mixer.create_filter(fx_id),
+            EffectsEnum::Reverb => mixer.create_reverb(fx_id),
+            EffectsEnum::Convolution => mixer.create_convolution(fx_id),
+            EffectsEnum::Chorus => mixer.create_chorus(fx_id),
+            EffectsEnum::Flanger => mixer.create_flanger(fx_id),
+            EffectsEnum::Phaser => mixer.create_phaser(fx_id),
+            EffectsEnum::Distortion => mixer.create_distortion(fx_id),
+            EffectsEnum::Bitcrusher => mixer.create_bitcrusher(fx_id),
+            EffectsEnum::Compressor => mixer.create_compressor(fx_id),
+            EffectsEnum::Limiter => mixer.create_limiter(fx_id),
+            EffectsEnum::Gate => mixer.create_gate(fx_id),
+            EffectsEnum::Equalizer => mixer.create_equalizer(fx_id),
+        });
     }
 
     pub fn remove_fx(&mut self, fx_id: u32) {
-        MIXER.with(|m| {
-            let mut mixer = m.lock().unwrap();
-            mixer.remove_fx(fx_id);
-        })
+        EventHandler::with_fx_mixer(&self.parts, fx_id, |mixer| mixer.remove_fx(fx_id));
     }
 
     pub fn edit_fx(&mut self, fx_id: u32, param_index: u32, value: f32) {
-        MIXER.with(|m| {
-            let mut mixer = m.lock().unwrap();
-            mixer.update_fx(fx_id, param_index, value);
-        })
+        EventHandler::with_fx_mixer(&self.parts, fx_id, |mixer| {
+            mixer.update_fx(fx_id, param_index, value)
+        });
+    }
+
+    fn with_bus_mixer<F: FnOnce(&mut Mixer)>(&self, bus: usize, f: F) {
+        if bus == 0 {
+            MIXER.with(|m| f(&mut m.lock().unwrap()));
+        } else if let Some(part) = self.parts.borrow_mut().get_mut(bus - 1) {
+            f(&mut part.mixer);
+        } else {
+            console::error_1(&format!("Cannot find fx bus {}", bus).into());
+        }
+    }
+
+    /// Chaîne qui contient l'effet, le bus maître en priorité
+    fn with_fx_mixer<F: FnOnce(&mut Mixer)>(parts: &RefCell<Vec<Part>>, fx_id: u32, f: F) {
+        let on_master = MIXER.with(|m| m.lock().unwrap().contains_fx(fx_id));
+        if on_master {
+            MIXER.with(|m| f(&mut m.lock().unwrap()));
+            return;
+        }
+
+        let mut parts = parts.borrow_mut();
+        if let Some(part) = parts.iter_mut().find(|p| p.mixer.contains_fx(fx_id)) {
+            f(&mut part.mixer);
+        }
     }
 
     pub fn process_sample_event(&mut self, sample_event: &Int32Array) {
@@ -216,7 +441,7 @@ impl EventHandler {
             };
             self.last_sample_event = new_event;
 
-            SAMPLE_MANAGER.with(|sm| {
+            let added = SAMPLE_MANAGER.with(|sm| {
                 let mut sm = sm.lock().unwrap();
 
                 SHARED_BUFFERS.with(|sb| {
@@ -234,22 +459,177 @@ impl EventHandler {
                                 self.last_sample_event.sample_id,
                                 sample_buffer.clone(),
                                 self.last_sample_event.length,
+                                self.last_sample_event.channels,
                                 self.last_sample_event.hq,
                             );
+                            return true;
                         }
                     }
-                });
+                    false
+                })
             });
 
+            // une convolution peut attendre ce sample comme réponse impulsionnelle
+            if added {
+                let sample_id = self.last_sample_event.sample_id;
+                MIXER.with(|m| m.lock().unwrap().sample_loaded(sample_id));
+                for part in self.parts.borrow_mut().iter_mut() {
+                    part.mixer.sample_loaded(sample_id);
+                }
+            }
+
             // et ici on peut utiliser self.last_sample_event
-            if let Some(sampler) = self
-                .samplers
-                .borrow_mut()
-                .iter_mut()
-                .find(|s| s.id == self.last_sample_event.sampler_id as u8)
-            {
+            if let Some(sampler) = Part::find_sampler_mut(
+                &mut self.parts.borrow_mut(),
+                self.last_sample_event.sampler_id as u8,
+            ) {
                 sampler.change_sample(self.last_sample_event.sample_id);
             }
         }
     }
+
+    pub fn process_control_events(&mut self, control: &ControlBuffers) -> u32 {
+        control.process_all_events(|dto| self.apply_control_event(dto))
+    }
+
+    fn apply_control_event(&mut self, dto: &ControlEventDto) {
+        match ControlTarget::try_from(dto.target) {
+            Ok(ControlTarget::Tuning) => TUNING.with(|t| {
+                t.lock().unwrap().update(dto.param, dto.index, dto.value);
+            }),
+            Ok(ControlTarget::Samples) => SAMPLE_MANAGER.with(|sm| {
+                sm.lock().unwrap().update(dto.param, dto.index, dto.value);
+            }),
+            Ok(ControlTarget::Arpeggiator) => {
+                self.arpeggiator.borrow_mut().update(dto.param, dto.value);
+            }
+            Ok(ControlTarget::Transport) => match dto.param {
+                0 => self.set_transport_playing(dto.value != 0.0),
+                _ => TRANSPORT.with(|t| t.lock().unwrap().update(dto.param, dto.value)),
+            },
+            Ok(ControlTarget::Recorder) => self.recorder.update(dto.param, self.sample_index),
+            Ok(ControlTarget::MidiPlayer) => {
+                self.smf_player
+                    .borrow_mut()
+                    .update(dto.param, dto.index, dto.value);
+            }
+            Ok(ControlTarget::MidiClock) => self.midi_clock.update(dto.param, dto.value),
+            Ok(ControlTarget::Sequencer) => {
+                self.sequencer
+                    .borrow_mut()
+                    .update(dto.param, dto.index, dto.value);
+            }
+            Ok(ControlTarget::DrumKit) => {
+                // La partie occupe les bits 16 et plus de l'index, la touche les bits bas
+                let part = (dto.index >> 16) as usize;
+                if let Some(part) = self.parts.borrow_mut().get_mut(part) {
+                    part.note_manager
+                        .drum_kit
+                        .update(dto.param, dto.index & 0xFFFF, dto.value);
+                }
+            }
+            Ok(ControlTarget::Part) => self.update_part(dto.param, dto.index, dto.value),
+            Ok(ControlTarget::Mpe) => self.mpe.update(dto.param, dto.value),
+            Ok(ControlTarget::CcMap) => self.cc_map.update(dto.param, dto.index, dto.value),
+            Err(e) => console::error_1(&e.into()),
+        }
+    }
+
+    /// La partie 0 est toujours présente et ne peut pas être retirée
+    fn update_part(&mut self, param: u32, index: u32, value: f32) {
+        let mut parts = self.parts.borrow_mut();
+
+        match param {
+            0 if parts.len() < MAX_PARTS => {
+                // Le processeur ne propage le tempo qu'à ses changements
+                let mut part = Part::new();
+                part.mixer
+                    .set_tempo(TRANSPORT.with(|t| t.lock().unwrap().bpm));
+                parts.push(part);
+            }
+            0 => console::error_1(&"Nombre maximal de parties atteint".into()),
+            1 if index > 0 && (index as usize) < parts.len() => {
+                parts.remove(index as usize);
+            }
+            1 => console::error_1(&format!("Cannot remove part {}", index).into()),
+            _ => match parts.get_mut(index as usize) {
+                Some(part) => part.update(param, value),
+                None => console::error_1(&format!("Cannot find part {}", index).into()),
+            },
+        }
+    }
+
+    /// Fichiers produits par le moteur, renvoyés à l'interface
+    /// Un seul fichier par bloc : l'interface lit chaque réponse avant d'en demander une autre
+    pub fn process_file_responses(&mut self, file: &FileBuffers) {
+        let (kind, bytes) = if self.recorder.export_requested {
+            self.recorder.export_requested = false;
+            (FileKind::MidiFile, self.recorder.to_midi_file().to_bytes())
+        } else if self.cc_map.export_requested {
+            self.cc_map.export_requested = false;
+            (FileKind::CcMappings, self.cc_map.to_bytes())
+        } else if let Some(curve) = self.take_eq_curve() {
+            (FileKind::EqCurve, curve)
+        } else {
+            return;
+        };
+
+        if let Err(e) = file.write_response(kind as u32, &bytes) {
+            console::error_1(&e.into());
+        }
+    }
+
+    fn take_eq_curve(&self) -> Option<Vec<u8>> {
+        MIXER
+            .with(|m| m.lock().unwrap().take_eq_curve())
+            .or_else(|| {
+                self.parts
+                    .borrow_mut()
+                    .iter_mut()
+                    .find_map(|part| part.mixer.take_eq_curve())
+            })
+    }
+
+    pub fn process_file_event(&mut self, file: &FileBuffers) {
+        let file_event_index = file.event.get_index(0) as u32;
+
+        if file_event_index == self.last_file_event.file_event_index {
+            return;
+        }
+
+        self.last_file_event = FileEvent {
+            file_event_index,
+            kind: file.event.get_index(1) as u32,
+            length: file.event.get_index(2) as u32,
+        };
+
+        let length = self.last_file_event.length.min(file.data.length());
+        let bytes = file.data.subarray(0, length).to_vec();
+
+        match FileKind::try_from(self.last_file_event.kind) {
+            Ok(FileKind::ScalaScale) => match ScalaScale::parse(&String::from_utf8_lossy(&bytes)) {
+                Ok(scale) => TUNING.with(|t| t.lock().unwrap().set_scale(scale)),
+                Err(e) => console::error_1(&e.into()),
+            },
+            Ok(FileKind::KeyboardMapping) => {
+                match KeyboardMapping::parse(&String::from_utf8_lossy(&bytes)) {
+                    Ok(mapping) => TUNING.with(|t| t.lock().unwrap().set_mapping(mapping)),
+                    Err(e) => console::error_1(&e.into()),
+                }
+            }
+            Ok(FileKind::MidiFile) => match MidiFile::parse(&bytes) {
+                Ok(file) => self.smf_player.borrow_mut().load(file),
+                Err(e) => console::error_1(&e.into()),
+            },
+            Ok(FileKind::CcMappings) => {
+                if let Err(e) = self.cc_map.load(&bytes) {
+                    console::error_1(&e.into());
+                }
+            }
+            Ok(FileKind::EqCurve) => {
+                console::error_1(&"Courbe d'égaliseur : fichier en lecture seule".into())
+            }
+            Err(e) => console::error_1(&e.into()),
+        }
+    }
 }
@@ -0,0 +1,361 @@
+use js_sys::Float32Array;
+use web_sys::console;
+
+use crate::utils::constants::{MAX_CC_MAPPINGS, REPORT_CC_LEARN_INDEX};
+
+/// Taille d'une affectation dans le format d'échange
+const MAPPING_RECORD_SIZE: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ControllerKind {
+    ControlChange = 0,
+    Nrpn = 1,
+}
+
+impl TryFrom<u8> for ControllerKind {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ControllerKind::ControlChange),
+            1 => Ok(ControllerKind::Nrpn),
+            _ => Err("Type de contrôleur inconnu"),
+        }
+    }
+}
+
+/// Paramètre piloté : une clé de sampler (`OscKey`) ou un paramètre d'effet du `Mixer`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MappingTarget {
+    Sampler = 0,
+    Fx = 1,
+}
+
+impl TryFrom<u8> for MappingTarget {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(MappingTarget::Sampler),
+            1 => Ok(MappingTarget::Fx),
+            _ => Err("Cible d'affectation inconnue"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MappingCurve {
+    Linear = 0,
+    /// Progression lente en début de course (fréquences, temps)
+    Exponential = 1,
+    Logarithmic = 2,
+    /// Bascule entre min et max à mi-course
+    Toggle = 3,
+}
+
+impl TryFrom<u8> for MappingCurve {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(MappingCurve::Linear),
+            1 => Ok(MappingCurve::Exponential),
+            2 => Ok(MappingCurve::Logarithmic),
+            3 => Ok(MappingCurve::Toggle),
+            _ => Err("Courbe d'affectation inconnue"),
+        }
+    }
+}
+
+impl MappingCurve {
+    fn apply(self, x: f32) -> f32 {
+        match self {
+            MappingCurve::Linear => x,
+            MappingCurve::Exponential => x * x,
+            MappingCurve::Logarithmic => x.sqrt(),
+            MappingCurve::Toggle => {
+                if x >= 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// Lien entre un contrôleur (canal, numéro de CC ou de NRPN) et un paramètre.
+/// `min` et `max` sont dans les unités attendues par la cible.
+#[derive(Debug, Clone, Copy)]
+pub struct CcMapping {
+    /// `None` pour tous les canaux
+    pub channel: Option<u8>,
+    pub kind: ControllerKind,
+    pub number: u16,
+    pub target: MappingTarget,
+    /// Id du sampler ou de l'effet
+    pub target_id: u32,
+    /// Clé du sampler ou index du paramètre de l'effet
+    pub target_param: u32,
+    pub min: f32,
+    pub max: f32,
+    pub curve: MappingCurve,
+    /// Faux tant qu'aucune cible n'a été choisie : un emplacement appris ne pilote rien
+    pub active: bool,
+}
+
+impl Default for CcMapping {
+    fn default() -> Self {
+        Self {
+            channel: None,
+            kind: ControllerKind::ControlChange,
+            number: 1,
+            target: MappingTarget::Sampler,
+            target_id: 0,
+            target_param: 0,
+            min: 0.0,
+            max: 1.0,
+            curve: MappingCurve::Linear,
+            active: false,
+        }
+    }
+}
+
+impl CcMapping {
+    fn matches(&self, channel: u8, kind: ControllerKind, number: u16) -> bool {
+        self.active
+            && self.kind == kind
+            && self.number == number
+            && self.channel.is_none_or(|c| c == channel)
+    }
+
+    /// `value` est la position normalisée du contrôleur, entre 0 et 1
+    pub fn scale(&self, value: f32) -> f32 {
+        self.min + (self.max - self.min) * self.curve.apply(value.clamp(0.0, 1.0))
+    }
+
+    fn write(&self, slot: usize, bytes: &mut Vec<u8>) {
+        bytes.push(slot as u8);
+        bytes.push(self.channel.unwrap_or(0xFF));
+        bytes.push(self.kind as u8);
+        bytes.push(self.curve as u8);
+        bytes.extend_from_slice(&self.number.to_le_bytes());
+        bytes.push(self.target as u8);
+        // Octet nul pour une affectation active, ce qui garde lisibles les anciens exports
+        bytes.push(!self.active as u8);
+        bytes.extend_from_slice(&self.target_id.to_le_bytes());
+        bytes.extend_from_slice(&self.target_param.to_le_bytes());
+        bytes.extend_from_slice(&self.min.to_le_bytes());
+        bytes.extend_from_slice(&self.max.to_le_bytes());
+    }
+
+    fn read(record: &[u8]) -> Result<(usize, Self), &'static str> {
+        let u32_at =
+            |i: usize| u32::from_le_bytes([record[i], record[i + 1], record[i + 2], record[i + 3]]);
+
+        let mapping = CcMapping {
+            channel: (record[1] != 0xFF).then_some(record[1] & 0x0F),
+            kind: ControllerKind::try_from(record[2])?,
+            curve: MappingCurve::try_from(record[3])?,
+            number: u16::from_le_bytes([record[4], record[5]]),
+            target: MappingTarget::try_from(record[6])?,
+            target_id: u32_at(8),
+            target_param: u32_at(12),
+            min: f32::from_bits(u32_at(16)),
+            max: f32::from_bits(u32_at(20)),
+            active: record[7] == 0,
+        };
+
+        Ok((record[0] as usize, mapping))
+    }
+}
+
+/// Table des affectations de contrôleurs, avec un mode d'apprentissage
+/// qui lie le prochain contrôleur reçu à l'emplacement choisi.
+pub struct CcMap {
+    mappings: [Option<CcMapping>; MAX_CC_MAPPINGS],
+    /// Emplacement en attente d'un contrôleur
+    learning: Option<usize>,
+    /// Dernier emplacement appris, renvoyé à l'interface par le buffer de rapport
+    last_learned: Option<usize>,
+    /// Un export est demandé, il sera envoyé par le canal de fichiers
+    pub export_requested: bool,
+}
+
+impl CcMap {
+    pub fn new() -> Self {
+        Self {
+            mappings: [None; MAX_CC_MAPPINGS],
+            learning: None,
+            last_learned: None,
+            export_requested: false,
+        }
+    }
+
+    /// `index` désigne l'emplacement de l'affectation
+    pub fn update(&mut self, param: u32, index: u32, value: f32) {
+        match param {
+            10 => {
+                if (index as usize) < MAX_CC_MAPPINGS {
+                    self.learning = Some(index as usize);
+                }
+                return;
+            }
+            11 => {
+                self.learning = None;
+                return;
+            }
+            12 => {
+                self.export_requested = true;
+                return;
+            }
+            13 => {
+                self.mappings = [None; MAX_CC_MAPPINGS];
+                return;
+            }
+            _ => {}
+        }
+
+        let Some(slot) = self.mappings.get_mut(index as usize) else {
+            return;
+        };
+
+        if param == 9 {
+            *slot = None;
+            return;
+        }
+
+        let mapping = slot.get_or_insert_with(CcMapping::default);
+        match param {
+            0 => {
+                if let Ok(target) = MappingTarget::try_from(value as u8) {
+                    mapping.target = target;
+                    mapping.active = true;
+                }
+            }
+            1 => {
+                mapping.target_id = value as u32;
+                mapping.active = true;
+            }
+            2 => {
+                mapping.target_param = value as u32;
+                mapping.active = true;
+            }
+            3 => {
+                mapping.channel = if value < 0.0 {
+                    None
+                } else {
+                    Some(value as u8 & 0x0F)
+                }
+            }
+            4 => {
+                if let Ok(kind) = ControllerKind::try_from(value as u8) {
+                    mapping.kind = kind
+                }
+            }
+            5 => mapping.number = value.clamp(0.0, 16383.0) as u16,
+            6 => mapping.min = value,
+            7 => mapping.max = value,
+            8 => {
+                if let Ok(curve) = MappingCurve::try_from(value as u8) {
+                    mapping.curve = curve
+                }
+            }
+            _ => console::error_1(&format!("Cannot update cc mapping {}", param).into()),
+        }
+    }
+
+    /// Traite un contrôleur reçu (valeur normalisée entre 0 et 1) : il est d'abord capturé
+    /// si un apprentissage est en cours, puis chaque affectation concernée est appliquée
+    pub fn process<F>(
+        &mut self,
+        channel: u8,
+        kind: ControllerKind,
+        number: u16,
+        value: f32,
+        mut apply: F,
+    ) where
+        F: FnMut(&CcMapping, f32),
+    {
+        if let Some(slot) = self.learning.take() {
+            let mapping = self.mappings[slot].get_or_insert_with(CcMapping::default);
+            mapping.channel = Some(channel);
+            mapping.kind = kind;
+            mapping.number = number;
+            self.last_learned = Some(slot);
+        }
+
+        for mapping in self.mappings.iter().flatten() {
+            if mapping.matches(channel, kind, number) {
+                apply(mapping, mapping.scale(value));
+            }
+        }
+    }
+
+    /// Emplacement en apprentissage (-1 si aucun), puis dernier emplacement appris
+    /// avec son canal, son type et son numéro de contrôleur
+    pub fn write_report(&self, report: &Float32Array) {
+        let learned = self
+            .last_learned
+            .and_then(|slot| self.mappings[slot].map(|m| (slot, m)));
+
+        let values = [
+            self.learning.map_or(-1.0, |slot| slot as f32),
+            learned.map_or(-1.0, |(slot, _)| slot as f32),
+            learned.map_or(-1.0, |(_, m)| m.channel.map_or(-1.0, |c| c as f32)),
+            learned.map_or(-1.0, |(_, m)| m.kind as u8 as f32),
+            learned.map_or(-1.0, |(_, m)| m.number as f32),
+        ];
+
+        for (i, value) in values.iter().enumerate() {
+            report.set_index(REPORT_CC_LEARN_INDEX + i as u32, *value);
+        }
+    }
+
+    /// Format d'échange, sauvegardé avec le patch : en-tête "CCMP", version,
+    /// nombre d'affectations puis un enregistrement fixe par affectation
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"CCMP");
+        bytes.push(1);
+        bytes.push(self.mappings.iter().flatten().count() as u8);
+
+        for (slot, mapping) in self.mappings.iter().enumerate() {
+            if let Some(mapping) = mapping {
+                mapping.write(slot, &mut bytes);
+            }
+        }
+
+        bytes
+    }
+
+    /// Remplace la table par celle d'un patch
+    pub fn load(&mut self, bytes: &[u8]) -> Result<(), &'static str> {
+        if bytes.len() < 6 || &bytes[0..4] != b"CCMP" {
+            return Err("Table d'affectations invalide");
+        }
+        if bytes[4] != 1 {
+            return Err("Version de table d'affectations non supportée");
+        }
+
+        let count = bytes[5] as usize;
+        let records = &bytes[6..];
+        if records.len() < count * MAPPING_RECORD_SIZE {
+            return Err("Table d'affectations tronquée");
+        }
+
+        let mut mappings = [None; MAX_CC_MAPPINGS];
+        for record in records.chunks_exact(MAPPING_RECORD_SIZE).take(count) {
+            let (slot, mapping) = CcMapping::read(record)?;
+            if let Some(entry) = mappings.get_mut(slot) {
+                *entry = Some(mapping);
+            }
+        }
+
+        self.mappings = mappings;
+        self.learning = None;
+        self.last_learned = None;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+/// Contrôleur décodé à partir des CC bruts d'un canal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ControllerEvent {
+    /// CC 7 bits, ou paire MSB/LSB (CC 0-31 et 32-63) assemblée sur 14 bits.
+    /// `value` est normalisée entre 0 et 1.
+    Control { number: u8, value: f32 },
+    /// NRPN, valeur 14 bits normalisée entre 0 et 1
+    Nrpn { number: u16, value: f32 },
+    /// RPN, valeur 14 bits brute : MSB en bits 7-13, LSB en bits 0-6
+    Rpn { number: u16, value: u16 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ParameterNumber {
+    None,
+    Rpn(u16),
+    Nrpn(u16),
+}
+
+/// Numéro « nul » qui désélectionne le paramètre (MSB et LSB à 127)
+const NULL_PARAMETER: u16 = 0x3FFF;
+
+#[derive(Debug, Clone, Copy)]
+struct ChannelControllers {
+    msb: [u8; 32],
+    /// Le contrôleur a déjà reçu un MSB, un CC 32-63 est alors son LSB
+    has_msb: [bool; 32],
+    /// Le contrôleur envoie aussi des LSB, sa valeur est normalisée sur 14 bits
+    high_resolution: [bool; 32],
+    parameter_msb: u8,
+    parameter_lsb: u8,
+    parameter: ParameterNumber,
+    /// Valeur courante de la saisie de données, sur 14 bits
+    data: u16,
+}
+
+impl ChannelControllers {
+    fn new() -> Self {
+        Self {
+            msb: [0; 32],
+            has_msb: [false; 32],
+            high_resolution: [false; 32],
+            parameter_msb: 127,
+            parameter_lsb: 127,
+            parameter: ParameterNumber::None,
+            data: 0,
+        }
+    }
+
+    fn select(&mut self, nrpn: bool) {
+        let number = ((self.parameter_msb as u16) << 7) | self.parameter_lsb as u16;
+        self.parameter = match (number, nrpn) {
+            (NULL_PARAMETER, _) => ParameterNumber::None,
+            (number, true) => ParameterNumber::Nrpn(number),
+            (number, false) => ParameterNumber::Rpn(number),
+        };
+        self.data = 0;
+    }
+
+    fn parameter_event(&self) -> Option<ControllerEvent> {
+        match self.parameter {
+            ParameterNumber::None => None,
+            ParameterNumber::Nrpn(number) => Some(ControllerEvent::Nrpn {
+                number,
+                value: self.data as f32 / 16383.0,
+            }),
+            ParameterNumber::Rpn(number) => Some(ControllerEvent::Rpn {
+                number,
+                value: self.data,
+            }),
+        }
+    }
+}
+
+/// Assemble les CC haute résolution et les séquences NRPN/RPN, canal par canal.
+/// Les CC de sélection et de saisie de données (6, 38, 96-101) ne sont pas transmis
+/// tels quels quand un paramètre est sélectionné.
+pub struct ControllerDecoder {
+    channels: [ChannelControllers; 16],
+}
+
+impl ControllerDecoder {
+    pub fn new() -> Self {
+        Self {
+            channels: [ChannelControllers::new(); 16],
+        }
+    }
+
+    pub fn decode(&mut self, channel: u8, number: u8, value: u8) -> Option<ControllerEvent> {
+        let state = &mut self.channels[channel as usize & 0x0F];
+        let value = value & 0x7F;
+
+        match number {
+            // Sélection NRPN (99 MSB, 98 LSB) et RPN (101 MSB, 100 LSB)
+            99 | 101 => {
+                state.parameter_msb = value;
+                state.select(number == 99);
+                None
+            }
+            98 | 100 => {
+                state.parameter_lsb = value;
+                state.select(number == 98);
+                None
+            }
+            // Saisie de données : le MSB remet le LSB à zéro
+            6 | 38 | 96 | 97 if state.parameter != ParameterNumber::None => {
+                state.data = match number {
+                    6 => (value as u16) << 7,
+                    38 => (state.data & !0x7F) | value as u16,
+                    96 => (state.data + 1).min(16383),
+                    _ => state.data.saturating_sub(1),
+                };
+                state.parameter_event()
+            }
+            0..=31 => {
+                let index = number as usize;
+                state.msb[index] = value;
+                state.has_msb[index] = true;
+
+                let value = if state.high_resolution[index] {
+                    ((value as u16) << 7) as f32 / 16383.0
+                } else {
+                    value as f32 / 127.0
+                };
+                Some(ControllerEvent::Control { number, value })
+            }
+            32..=63 if state.has_msb[number as usize - 32] => {
+                let index = number as usize - 32;
+                state.high_resolution[index] = true;
+
+                let combined = ((state.msb[index] as u16) << 7) | value as u16;
+                Some(ControllerEvent::Control {
+                    number: index as u8,
+                    value: combined as f32 / 16383.0,
+                })
+            }
+            _ => Some(ControllerEvent::Control {
+                number,
+                value: value as f32 / 127.0,
+            }),
+        }
+    }
+}
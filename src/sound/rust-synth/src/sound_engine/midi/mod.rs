@@ -0,0 +1,5 @@
+pub mod cc_map;
+pub mod controllers;
+pub mod mpe;
+pub mod recorder;
+pub mod smf;
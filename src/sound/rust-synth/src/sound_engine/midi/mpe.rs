@@ -0,0 +1,143 @@
+use web_sys::console;
+
+use crate::utils::constants::PITCH_BEND_RANGE;
+
+/// Zone MPE : un canal maître et des canaux membres, un par note jouée
+#[derive(Debug, Clone, Copy)]
+pub struct MpeZone {
+    /// Nombre de canaux membres, 0 désactive la zone
+    pub member_count: u8,
+    /// Amplitude du pitch bend des canaux membres, en demi-tons
+    pub bend_range: f32,
+    /// Amplitude du pitch bend du canal maître, en demi-tons
+    pub master_bend_range: f32,
+}
+
+impl MpeZone {
+    fn new() -> Self {
+        Self {
+            member_count: 0,
+            bend_range: 48.0,
+            master_bend_range: PITCH_BEND_RANGE,
+        }
+    }
+}
+
+/// Place d'un canal dans la configuration MPE
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelRole {
+    /// Canal maître : ses messages s'appliquent à toute la zone
+    Master {
+        members: std::ops::RangeInclusive<u8>,
+        bend_range: f32,
+    },
+    /// Canal membre : ses messages ne concernent que la note qu'il porte
+    Member { bend_range: f32 },
+    /// Hors zone, comportement MIDI classique par canal
+    Channel { bend_range: f32 },
+}
+
+/// Zone basse (maître sur le canal 1, membres au-dessus) et zone haute
+/// (maître sur le canal 16, membres en dessous)
+pub struct MpeConfig {
+    pub lower: MpeZone,
+    pub upper: MpeZone,
+    /// Amplitude du pitch bend des canaux hors zone, en demi-tons
+    channel_bend_ranges: [f32; 16],
+}
+
+impl MpeConfig {
+    pub fn new() -> Self {
+        Self {
+            lower: MpeZone::new(),
+            upper: MpeZone::new(),
+            channel_bend_ranges: [PITCH_BEND_RANGE; 16],
+        }
+    }
+
+    fn lower_members(&self) -> std::ops::RangeInclusive<u8> {
+        1..=self.lower.member_count
+    }
+
+    fn upper_members(&self) -> std::ops::RangeInclusive<u8> {
+        15 - self.upper.member_count..=14
+    }
+
+    pub fn role(&self, channel: u8) -> ChannelRole {
+        if self.lower.member_count > 0 {
+            if channel == 0 {
+                return ChannelRole::Master {
+                    members: self.lower_members(),
+                    bend_range: self.lower.master_bend_range,
+                };
+            }
+            if self.lower_members().contains(&channel) {
+                return ChannelRole::Member {
+                    bend_range: self.lower.bend_range,
+                };
+            }
+        }
+
+        if self.upper.member_count > 0 {
+            if channel == 15 {
+                return ChannelRole::Master {
+                    members: self.upper_members(),
+                    bend_range: self.upper.master_bend_range,
+                };
+            }
+            if self.upper_members().contains(&channel) {
+                return ChannelRole::Member {
+                    bend_range: self.upper.bend_range,
+                };
+            }
+        }
+
+        ChannelRole::Channel {
+            bend_range: self.channel_bend_ranges[channel as usize & 0x0F],
+        }
+    }
+
+    /// RPN 0 : reçu sur un canal membre, il règle l'amplitude de toute la zone
+    pub fn set_bend_range(&mut self, channel: u8, semitones: f32) {
+        let semitones = semitones.clamp(0.0, 96.0);
+        let lower_active = self.lower.member_count > 0;
+        let upper_active = self.upper.member_count > 0;
+
+        if lower_active && channel == 0 {
+            self.lower.master_bend_range = semitones;
+        } else if lower_active && self.lower_members().contains(&channel) {
+            self.lower.bend_range = semitones;
+        } else if upper_active && channel == 15 {
+            self.upper.master_bend_range = semitones;
+        } else if upper_active && self.upper_members().contains(&channel) {
+            self.upper.bend_range = semitones;
+        } else if let Some(range) = self.channel_bend_ranges.get_mut(channel as usize) {
+            *range = semitones;
+        }
+    }
+
+    /// Une zone qui s'agrandit réduit l'autre, comme le prévoit la norme MPE
+    pub fn update(&mut self, param: u32, value: f32) {
+        match param {
+            0 => {
+                self.lower.member_count = value.clamp(0.0, 15.0) as u8;
+                self.upper.member_count = self
+                    .upper
+                    .member_count
+                    .min(14u8.saturating_sub(self.lower.member_count));
+            }
+            1 => {
+                self.upper.member_count = value.clamp(0.0, 15.0) as u8;
+                self.lower.member_count = self
+                    .lower
+                    .member_count
+                    .min(14u8.saturating_sub(self.upper.member_count));
+            }
+            2 => self.lower.bend_range = value.clamp(0.0, 96.0),
+            3 => self.upper.bend_range = value.clamp(0.0, 96.0),
+            4 => self.lower.master_bend_range = value.clamp(0.0, 96.0),
+            5 => self.upper.master_bend_range = value.clamp(0.0, 96.0),
+            _ => console::error_1(&format!("Cannot update MPE {}", param).into()),
+        }
+    }
+}
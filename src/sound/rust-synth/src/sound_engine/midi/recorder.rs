@@ -0,0 +1,99 @@
+use web_sys::console;
+
+use crate::{
+    global::TRANSPORT,
+    sound_engine::midi::smf::{MidiFile, SmfEvent, SmfEventKind, SmfTrack},
+    utils::constants::SAMPLE_RATE,
+};
+
+/// Résolution des fichiers exportés
+const RECORD_TICKS_PER_QUARTER: u16 = 480;
+
+#[derive(Debug, Clone, Copy)]
+struct RecordedEvent {
+    sample_index: u64,
+    kind: SmfEventKind,
+}
+
+/// Enregistre les notes et CC reçus dans la file MIDI, datés à l'échantillon,
+/// et les exporte en fichier MIDI standard de type 0 au tempo du transport.
+pub struct MidiRecorder {
+    pub recording: bool,
+    start_sample_index: u64,
+    events: Vec<RecordedEvent>,
+    /// Un export est demandé, il sera envoyé par le canal de fichiers
+    pub export_requested: bool,
+}
+
+impl MidiRecorder {
+    pub fn new() -> Self {
+        Self {
+            recording: false,
+            start_sample_index: 0,
+            events: Vec::new(),
+            export_requested: false,
+        }
+    }
+
+    /// `sample_index` est l'horloge du processeur au moment de la commande
+    pub fn update(&mut self, param: u32, sample_index: u64) {
+        match param {
+            0 => {
+                self.events.clear();
+                self.start_sample_index = sample_index;
+                self.recording = true;
+            }
+            1 => self.recording = false,
+            2 => self.export_requested = true,
+            3 => self.events.clear(),
+            _ => console::error_1(&format!("Cannot update midi recorder {}", param).into()),
+        }
+    }
+
+    pub fn record(&mut self, sample_index: u64, kind: SmfEventKind) {
+        if self.recording {
+            self.events.push(RecordedEvent { sample_index, kind });
+        }
+    }
+
+    pub fn to_midi_file(&self) -> MidiFile {
+        let (bpm, numerator, denominator) = TRANSPORT.with(|t| {
+            let transport = t.lock().unwrap();
+            (transport.bpm, transport.numerator, transport.denominator)
+        });
+
+        let samples_per_tick =
+            60.0 / bpm as f64 * SAMPLE_RATE as f64 / RECORD_TICKS_PER_QUARTER as f64;
+
+        let mut events = vec![
+            SmfEvent {
+                tick: 0,
+                kind: SmfEventKind::Tempo((60_000_000.0 / bpm) as u32),
+            },
+            SmfEvent {
+                tick: 0,
+                kind: SmfEventKind::TimeSignature {
+                    numerator,
+                    denominator,
+                },
+            },
+        ];
+
+        events.extend(self.events.iter().map(|e| {
+            let elapsed = e.sample_index.saturating_sub(self.start_sample_index);
+            SmfEvent {
+                tick: (elapsed as f64 / samples_per_tick).round() as u64,
+                kind: e.kind,
+            }
+        }));
+
+        MidiFile {
+            format: 0,
+            ticks_per_quarter: RECORD_TICKS_PER_QUARTER,
+            tracks: vec![SmfTrack {
+                name: "Enregistrement".into(),
+                events,
+            }],
+        }
+    }
+}
@@ -0,0 +1,325 @@
+/// Événement d'un fichier MIDI standard, daté en ticks depuis le début de sa piste
+#[derive(Debug, Clone, Copy)]
+pub struct SmfEvent {
+    pub tick: u64,
+    pub kind: SmfEventKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SmfEventKind {
+    NoteOn {
+        channel: u8,
+        note: u8,
+        velocity: u8,
+    },
+    NoteOff {
+        channel: u8,
+        note: u8,
+    },
+    ControlChange {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+    /// Microsecondes par noire
+    Tempo(u32),
+    TimeSignature {
+        numerator: u8,
+        denominator: u8,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SmfTrack {
+    pub name: String,
+    pub events: Vec<SmfEvent>,
+}
+
+/// Fichier MIDI standard (.mid) de type 0 ou 1. Seules les notes et les méta-événements
+/// de tempo et de signature sont conservés, le reste est lu puis ignoré.
+#[derive(Debug, Clone)]
+pub struct MidiFile {
+    pub format: u16,
+    pub ticks_per_quarter: u16,
+    pub tracks: Vec<SmfTrack>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], &'static str> {
+        let end = self.pos.checked_add(length).ok_or("Fichier MIDI tronqué")?;
+        let slice = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or("Fichier MIDI tronqué")?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, &'static str> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, &'static str> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, &'static str> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Quantité à longueur variable : 7 bits par octet, bit de poids fort = suite
+    fn var_len(&mut self) -> Result<u32, &'static str> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("Quantité à longueur variable invalide")
+    }
+}
+
+impl MidiFile {
+    /// Sérialise le fichier ; les événements de chaque piste doivent être triés par date
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"MThd");
+        bytes.extend_from_slice(&6u32.to_be_bytes());
+        bytes.extend_from_slice(&self.format.to_be_bytes());
+        bytes.extend_from_slice(&(self.tracks.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&self.ticks_per_quarter.to_be_bytes());
+
+        for track in self.tracks.iter() {
+            let data = MidiFile::write_track(track);
+            bytes.extend_from_slice(b"MTrk");
+            bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(&data);
+        }
+
+        bytes
+    }
+
+    fn write_track(track: &SmfTrack) -> Vec<u8> {
+        let mut data = Vec::new();
+
+        if !track.name.is_empty() {
+            MidiFile::write_var_len(&mut data, 0);
+            data.extend_from_slice(&[0xFF, 0x03]);
+            MidiFile::write_var_len(&mut data, track.name.len() as u32);
+            data.extend_from_slice(track.name.as_bytes());
+        }
+
+        let mut last_tick = 0;
+        for event in track.events.iter() {
+            MidiFile::write_var_len(&mut data, (event.tick - last_tick) as u32);
+            last_tick = event.tick;
+
+            match event.kind {
+                SmfEventKind::NoteOn {
+                    channel,
+                    note,
+                    velocity,
+                } => data.extend_from_slice(&[0x90 | channel, note, velocity]),
+                SmfEventKind::NoteOff { channel, note } => {
+                    data.extend_from_slice(&[0x80 | channel, note, 0])
+                }
+                SmfEventKind::ControlChange {
+                    channel,
+                    controller,
+                    value,
+                } => data.extend_from_slice(&[0xB0 | channel, controller, value]),
+                SmfEventKind::Tempo(micros) => {
+                    data.extend_from_slice(&[0xFF, 0x51, 0x03]);
+                    data.extend_from_slice(&micros.to_be_bytes()[1..]);
+                }
+                SmfEventKind::TimeSignature {
+                    numerator,
+                    denominator,
+                } => data.extend_from_slice(&[
+                    0xFF,
+                    0x58,
+                    0x04,
+                    numerator,
+                    denominator.trailing_zeros() as u8,
+                    24,
+                    8,
+                ]),
+            }
+        }
+
+        // Fin de piste
+        data.extend_from_slice(&[0x00, 0xFF, 0x2F, 0x00]);
+        data
+    }
+
+    /// Quantité à longueur variable, octets de poids fort en premier
+    fn write_var_len(data: &mut Vec<u8>, value: u32) {
+        let mut groups = vec![(value & 0x7F) as u8];
+        let mut rest = value >> 7;
+        while rest > 0 {
+            groups.push((rest & 0x7F) as u8 | 0x80);
+            rest >>= 7;
+        }
+
+        data.extend(groups.iter().rev());
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, &'static str> {
+        let mut reader = Reader::new(bytes);
+
+        if reader.take(4)? != b"MThd" {
+            return Err("En-tête MThd absent");
+        }
+        let header_length = reader.u32()? as usize;
+        let mut header = Reader::new(reader.take(header_length)?);
+
+        let format = header.u16()?;
+        let track_count = header.u16()?;
+        let division = header.u16()?;
+
+        if format > 1 {
+            return Err("Seuls les fichiers MIDI de type 0 et 1 sont pris en charge");
+        }
+        if division & 0x8000 != 0 || division == 0 {
+            return Err("Division temporelle SMPTE non prise en charge");
+        }
+
+        let mut tracks = Vec::with_capacity(track_count as usize);
+        while tracks.len() < track_count as usize && !reader.is_empty() {
+            let chunk_id = reader.take(4)?;
+            let length = reader.u32()? as usize;
+            let data = reader.take(length)?;
+
+            // Les blocs inconnus doivent être ignorés
+            if chunk_id == b"MTrk" {
+                tracks.push(MidiFile::parse_track(data)?);
+            }
+        }
+
+        Ok(Self {
+            format,
+            ticks_per_quarter: division,
+            tracks,
+        })
+    }
+
+    fn parse_track(data: &[u8]) -> Result<SmfTrack, &'static str> {
+        let mut reader = Reader::new(data);
+        let mut track = SmfTrack::default();
+        let mut tick = 0u64;
+        let mut running_status = 0u8;
+
+        while !reader.is_empty() {
+            tick += reader.var_len()? as u64;
+
+            let mut status = reader.u8()?;
+            let first_data = if status & 0x80 == 0 {
+                // Running status : l'octet lu est déjà la première donnée
+                if running_status == 0 {
+                    return Err("Running status sans statut précédent");
+                }
+                let data = status;
+                status = running_status;
+                Some(data)
+            } else {
+                None
+            };
+
+            match status {
+                0xFF => {
+                    // Méta-événements et sysex annulent le running status
+                    running_status = 0;
+                    let meta_type = reader.u8()?;
+                    let length = reader.var_len()? as usize;
+                    let payload = reader.take(length)?;
+
+                    match meta_type {
+                        0x03 => track.name = String::from_utf8_lossy(payload).into_owned(),
+                        0x2F => break,
+                        0x51 if length == 3 => {
+                            let micros = (payload[0] as u32) << 16
+                                | (payload[1] as u32) << 8
+                                | payload[2] as u32;
+                            if micros == 0 {
+                                return Err("Tempo nul");
+                            }
+                            track.events.push(SmfEvent {
+                                tick,
+                                kind: SmfEventKind::Tempo(micros),
+                            });
+                        }
+                        0x58 if length >= 2 => track.events.push(SmfEvent {
+                            tick,
+                            kind: SmfEventKind::TimeSignature {
+                                numerator: payload[0],
+                                denominator: 1u8.checked_shl(payload[1] as u32).unwrap_or(4),
+                            },
+                        }),
+                        _ => {}
+                    }
+                }
+                0xF0 | 0xF7 => {
+                    running_status = 0;
+                    let length = reader.var_len()? as usize;
+                    reader.take(length)?;
+                }
+                0x80..=0xEF => {
+                    running_status = status;
+
+                    let data_1 = match first_data {
+                        Some(data) => data,
+                        None => reader.u8()?,
+                    };
+                    // Changement de programme et pression de canal n'ont qu'une donnée
+                    let data_2 = match status & 0xF0 {
+                        0xC0 | 0xD0 => 0,
+                        _ => reader.u8()?,
+                    };
+
+                    let channel = status & 0x0F;
+                    let kind = match status & 0xF0 {
+                        0x90 if data_2 > 0 => Some(SmfEventKind::NoteOn {
+                            channel,
+                            note: data_1,
+                            velocity: data_2,
+                        }),
+                        0x80 | 0x90 => Some(SmfEventKind::NoteOff {
+                            channel,
+                            note: data_1,
+                        }),
+                        0xB0 => Some(SmfEventKind::ControlChange {
+                            channel,
+                            controller: data_1,
+                            value: data_2,
+                        }),
+                        _ => None,
+                    };
+
+                    if let Some(kind) = kind {
+                        track.events.push(SmfEvent { tick, kind });
+                    }
+                }
+                _ => return Err("Statut MIDI inattendu dans la piste"),
+            }
+        }
+
+        Ok(track)
+    }
+}
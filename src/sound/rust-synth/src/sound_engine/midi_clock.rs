@@ -0,0 +1,158 @@
+use web_sys::console;
+
+use crate::{
+    global::TRANSPORT,
+    shared_memory::shared_buffers::MidiBuffers,
+    utils::{
+        constants::{MIDI_CLOCK_PPQ, MIDI_CLOCK_WINDOW, SAMPLE_RATE},
+        types::EventType,
+    },
+};
+
+/// Synchronisation par horloge MIDI (24 impulsions par noire).
+/// En réception, le tempo du transport suit l'horloge externe, lissé sur une fenêtre d'impulsions ;
+/// en émission, les impulsions et messages de transport sont écrits dans la file MIDI sortante.
+pub struct MidiClock {
+    pub receive: bool,
+    pub send: bool,
+
+    /// Instants (en échantillons) des dernières impulsions reçues, en anneau
+    tick_times: [u64; MIDI_CLOCK_WINDOW],
+    tick_count: usize,
+    smoothed_bpm: Option<f32>,
+
+    sent_playing: bool,
+    /// Dernière impulsion émise, en impulsions depuis le début du morceau
+    sent_tick: i64,
+}
+
+impl MidiClock {
+    pub fn new() -> Self {
+        Self {
+            receive: false,
+            send: false,
+            tick_times: [0; MIDI_CLOCK_WINDOW],
+            tick_count: 0,
+            smoothed_bpm: None,
+            sent_playing: false,
+            sent_tick: 0,
+        }
+    }
+
+    pub fn update(&mut self, param: u32, value: f32) {
+        match param {
+            0 => {
+                self.receive = value != 0.0;
+                self.reset_measure();
+            }
+            1 => self.send = value != 0.0,
+            _ => console::error_1(&format!("Cannot update midi clock {}", param).into()),
+        }
+    }
+
+    fn reset_measure(&mut self) {
+        self.tick_count = 0;
+        self.smoothed_bpm = None;
+    }
+
+    /// Impulsion reçue à l'instant `sample_index`. Les événements n'étant lus qu'entre deux blocs,
+    /// le tempo est mesuré sur toute la fenêtre puis lissé pour absorber cette gigue.
+    pub fn receive_tick(&mut self, sample_index: u64) {
+        if !self.receive {
+            return;
+        }
+
+        if self.tick_count > 0 {
+            let last = self.tick_times[(self.tick_count - 1) % MIDI_CLOCK_WINDOW];
+            // Plus d'une seconde sans impulsion : l'horloge s'est arrêtée
+            if sample_index.saturating_sub(last) > SAMPLE_RATE as u64 {
+                self.reset_measure();
+            }
+        }
+
+        self.tick_times[self.tick_count % MIDI_CLOCK_WINDOW] = sample_index;
+        self.tick_count += 1;
+
+        if self.tick_count < MIDI_CLOCK_PPQ as usize {
+            return;
+        }
+
+        let span = self.tick_count.min(MIDI_CLOCK_WINDOW);
+        let first = self.tick_times[(self.tick_count - span) % MIDI_CLOCK_WINDOW];
+        let elapsed = sample_index.saturating_sub(first);
+        if elapsed == 0 {
+            return;
+        }
+
+        let beats = (span - 1) as f32 / MIDI_CLOCK_PPQ as f32;
+        let bpm = beats * 60.0 * SAMPLE_RATE / elapsed as f32;
+
+        let smoothed = match self.smoothed_bpm {
+            Some(previous) => previous + 0.1 * (bpm - previous),
+            None => bpm,
+        };
+        self.smoothed_bpm = Some(smoothed);
+
+        TRANSPORT.with(|t| t.lock().unwrap().bpm = smoothed.clamp(20.0, 999.0));
+    }
+
+    /// Écrit dans la file sortante les messages correspondant à l'avancée du transport
+    pub fn send_events(&mut self, midi_out: &MidiBuffers) {
+        if !self.send {
+            return;
+        }
+
+        let (playing, beats) = TRANSPORT.with(|t| {
+            let transport = t.lock().unwrap();
+            (transport.playing, transport.beats)
+        });
+        let tick = (beats * MIDI_CLOCK_PPQ as f64).floor() as i64;
+
+        if playing != self.sent_playing {
+            self.sent_playing = playing;
+
+            if !playing {
+                midi_out.enqueue_event(EventType::TransportStop as u8, 0, 0);
+                return;
+            }
+
+            if tick == 0 {
+                midi_out.enqueue_event(EventType::TransportStart as u8, 0, 0);
+            } else {
+                self.send_song_position(midi_out, beats);
+                midi_out.enqueue_event(EventType::TransportContinue as u8, 0, 0);
+            }
+            self.sent_tick = tick;
+            return;
+        }
+
+        if !playing {
+            return;
+        }
+
+        // Saut de position (relocalisation) : on annonce la nouvelle position au lieu
+        // d'envoyer une rafale d'impulsions
+        if tick < self.sent_tick || tick - self.sent_tick > MIDI_CLOCK_PPQ as i64 {
+            self.send_song_position(midi_out, beats);
+            self.sent_tick = tick;
+            return;
+        }
+
+        while self.sent_tick < tick {
+            if !midi_out.enqueue_event(EventType::Clock as u8, 0, 0) {
+                break;
+            }
+            self.sent_tick += 1;
+        }
+    }
+
+    /// La position est exprimée en doubles croches sur 14 bits
+    fn send_song_position(&self, midi_out: &MidiBuffers, beats: f64) {
+        let position = ((beats * 4.0).floor() as u32).min(0x3FFF);
+        midi_out.enqueue_event(
+            EventType::SongPosition as u8,
+            (position & 0x7F) as u8,
+            (position >> 7) as u8,
+        );
+    }
+}
@@ -1,4 +1,9 @@
 pub mod dsp;
 pub mod event_handler;
+pub mod midi;
+pub mod midi_clock;
 pub mod processor;
+pub mod sequencing;
 pub mod synthetizer;
+pub mod transport;
+pub mod tuning;
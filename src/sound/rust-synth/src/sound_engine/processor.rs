@@ -3,40 +3,57 @@ use std::{cell::RefCell, rc::Rc};
 use web_sys::console;
 
 use crate::{
-    global::MIXER,
+    global::{MIXER, TRANSPORT},
     shared_memory::ring_buffer_manager::RingBufferManager,
     sound_engine::{
         event_handler::{self, EventHandler},
-        synthetizer::{
-            note_manager::{self, NoteManager},
-            sampler::{self, Sampler},
+        sequencing::{
+            arpeggiator::Arpeggiator, smf_player::SmfPlayer, step_sequencer::StepSequencer,
         },
+        synthetizer::part::Part,
     },
-    utils::constants::PROCESSING_BUFFER_SIZE,
+    utils::constants::{MASTER_GAIN, PROCESSING_BUFFER_SIZE},
 };
 
 pub struct AudioProcessor {
-    pub note_manager: Rc<RefCell<NoteManager>>,
-    pub samplers: Rc<RefCell<Vec<Sampler>>>,
+    /// Parties du jeu multitimbral, la partie 0 écoute tous les canaux
+    pub parts: Rc<RefCell<Vec<Part>>>,
+    pub arpeggiator: Rc<RefCell<Arpeggiator>>,
+    pub sequencer: Rc<RefCell<StepSequencer>>,
+    pub smf_player: Rc<RefCell<SmfPlayer>>,
     pub event_handler: EventHandler,
     pub global_sample_index: u64,
     pub processing_buffer: Vec<f32>, // Alloué une seule fois
+    pub direct_buffer: Vec<f32>,     // Pads en sortie directe, hors effets
     pub processing_buffer_size: usize,
+    /// Dernier tempo transmis aux effets synchronisés
+    pub tempo: f32,
 }
 
 impl AudioProcessor {
     pub fn new() -> Self {
-        let note_manager = Rc::new(RefCell::new(NoteManager::new()));
-        let samplers = Rc::new(RefCell::new(Vec::new()));
-        let event_handler = EventHandler::new(Rc::clone(&note_manager), Rc::clone(&samplers));
+        let parts = Rc::new(RefCell::new(vec![Part::new()]));
+        let arpeggiator = Rc::new(RefCell::new(Arpeggiator::new()));
+        let sequencer = Rc::new(RefCell::new(StepSequencer::new()));
+        let smf_player = Rc::new(RefCell::new(SmfPlayer::new()));
+        let event_handler = EventHandler::new(
+            Rc::clone(&parts),
+            Rc::clone(&arpeggiator),
+            Rc::clone(&sequencer),
+            Rc::clone(&smf_player),
+        );
 
         Self {
-            note_manager,
-            samplers,
+            parts,
+            arpeggiator,
+            sequencer,
+            smf_player,
             event_handler,
             global_sample_index: 0,
             processing_buffer: vec![0.0; PROCESSING_BUFFER_SIZE * 2],
+            direct_buffer: vec![0.0; PROCESSING_BUFFER_SIZE * 2],
             processing_buffer_size: PROCESSING_BUFFER_SIZE,
+            tempo: 0.0,
         }
     }
 
@@ -59,38 +76,74 @@ impl AudioProcessor {
         }
 
         self.global_sample_index += frame_count as u64; // C'est le nombre de frames
+
+        let bpm = TRANSPORT.with(|t| t.lock().unwrap().bpm);
+        if bpm != self.tempo {
+            self.tempo = bpm;
+            MIXER.with(|mix| mix.lock().unwrap().set_tempo(bpm));
+            for part in self.parts.borrow_mut().iter_mut() {
+                part.mixer.set_tempo(bpm);
+            }
+        }
+
         let samples_slice = &mut self.processing_buffer[0..num_elements_f32 as usize];
+        let direct_slice = &mut self.direct_buffer[0..num_elements_f32 as usize];
 
-        self.note_manager.borrow_mut().generate_raw_samples(
-            samples_slice,
-            frame_count as usize, // Passe le nombre de frames à generate_raw_samples
-            &self.samplers.borrow(),
-        );
+        // Rendu par blocs jusqu'au prochain événement du séquenceur, de l'arpégiateur
+        // ou du lecteur de fichier MIDI
+        let frame_count = frame_count as usize;
+        let mut parts = self.parts.borrow_mut();
+        let mut offset = 0;
+        while offset < frame_count {
+            let mut arpeggiator = self.arpeggiator.borrow_mut();
+            let mut sequencer = self.sequencer.borrow_mut();
+            let mut smf_player = self.smf_player.borrow_mut();
+            let chunk = arpeggiator
+                .frames_until_next_event()
+                .min(sequencer.frames_until_next_event())
+                .min(smf_player.frames_until_next_event())
+                .min(frame_count - offset);
+
+            if chunk > 0 {
+                for part in parts.iter_mut() {
+                    part.render(offset, chunk);
+                }
+            }
 
-        AudioProcessor::apply_final_mixing(samples_slice, &self.samplers);
+            TRANSPORT.with(|t| t.lock().unwrap().advance(chunk));
+
+            sequencer.advance(chunk, &mut parts, |dto, parts| Part::dispatch(parts, dto));
+            arpeggiator.advance(chunk, |dto| Part::dispatch(&mut parts, dto));
+            smf_player.advance(chunk, |dto| Part::dispatch(&mut parts, dto));
+
+            offset += chunk;
+        }
+
+        // Chaque partie passe par sa propre chaîne d'effets avant le bus maître
+        samples_slice.fill(0.0);
+        direct_slice.fill(0.0);
+        for part in parts.iter_mut() {
+            part.mix_into(samples_slice, direct_slice);
+        }
+
+        AudioProcessor::apply_final_mixing(samples_slice, direct_slice);
 
         ring_buffer_manager.write_samples(samples_slice);
     }
 
-    pub fn apply_final_mixing(raw_samples: &mut [f32], samplers: &RefCell<Vec<Sampler>>) {
+    pub fn apply_final_mixing(raw_samples: &mut [f32], direct_samples: &[f32]) {
         // Option 1: Boucle for classique (recommandée pour l'indexation par pas de 2)
         for i in (0..raw_samples.len()).step_by(2) {
             let mut mixed_l = raw_samples[i];
             let mut mixed_r = raw_samples[i + 1];
 
-            // Normalisation par nombre d'samplers
-            if !samplers.borrow().is_empty() {
-                let osc_count = samplers.borrow().len() as f32;
-                mixed_l /= osc_count;
-                mixed_r /= osc_count;
-            }
-
             MIXER.with(|mix| {
                 mix.lock().unwrap().render(&mut mixed_l, &mut mixed_r);
             });
 
-            mixed_l *= 0.1;
-            mixed_r *= 0.1;
+            // La sortie directe rejoint le bus après les effets
+            mixed_l = (mixed_l + direct_samples[i]) * MASTER_GAIN;
+            mixed_r = (mixed_r + direct_samples[i + 1]) * MASTER_GAIN;
 
             // Réécrire dans le Vec
             raw_samples[i] = mixed_l;
@@ -0,0 +1,299 @@
+use web_sys::console;
+
+use crate::{
+    global::{RANDOM, TRANSPORT},
+    utils::types::NoteDTO,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArpMode {
+    Up = 0,
+    Down = 1,
+    UpDown = 2,
+    Random = 3,
+    AsPlayed = 4,
+}
+
+impl TryFrom<u8> for ArpMode {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ArpMode::Up),
+            1 => Ok(ArpMode::Down),
+            2 => Ok(ArpMode::UpDown),
+            3 => Ok(ArpMode::Random),
+            4 => Ok(ArpMode::AsPlayed),
+            _ => Err("Mode d'arpège inconnu"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct HeldNote {
+    value: u8,
+    velocity: u8,
+    channel: u8,
+    /// Touche encore enfoncée (une note verrouillée par le latch peut être relâchée)
+    pressed: bool,
+}
+
+impl HeldNote {
+    fn release(&self) -> NoteDTO {
+        NoteDTO {
+            value: self.value,
+            velocity: 0,
+            channel: self.channel,
+        }
+    }
+}
+
+/// Arpégiateur placé entre la file MIDI et le `NoteManager`.
+/// Les notes tenues sont transformées en événements datés à l'échantillon près :
+/// le processeur rend l'audio par blocs jusqu'au prochain événement.
+pub struct Arpeggiator {
+    pub enabled: bool,
+    mode: ArpMode,
+    octaves: u8,
+    /// Durée d'un pas en temps (0.25 = double croche)
+    rate: f32,
+    /// Proportion du pas pendant laquelle la note est tenue
+    gate: f32,
+    /// Retard des pas impairs, entre 0 et 1
+    swing: f32,
+    latch: bool,
+
+    held: Vec<HeldNote>,
+    pattern: Vec<HeldNote>,
+    step_index: usize,
+    /// Échantillons restants avant le prochain pas
+    until_step: f64,
+    /// Note en cours et échantillons restants avant son relâchement
+    playing: Option<(HeldNote, f64)>,
+}
+
+impl Arpeggiator {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            mode: ArpMode::Up,
+            octaves: 1,
+            rate: 0.25,
+            gate: 0.5,
+            swing: 0.0,
+            latch: false,
+            held: Vec::new(),
+            pattern: Vec::new(),
+            step_index: 0,
+            until_step: 0.0,
+            playing: None,
+        }
+    }
+
+    pub fn update(&mut self, param: u32, value: f32) {
+        match param {
+            0 => self.enabled = value != 0.0,
+            1 => match ArpMode::try_from(value as u8) {
+                Ok(mode) => {
+                    self.mode = mode;
+                    self.rebuild_pattern();
+                }
+                Err(e) => console::error_1(&e.into()),
+            },
+            2 => {
+                self.octaves = (value as u8).clamp(1, 4);
+                self.rebuild_pattern();
+            }
+            3 => self.rate = value.max(1.0 / 64.0),
+            4 => self.gate = value.clamp(0.01, 1.0),
+            5 => self.swing = value.clamp(0.0, 1.0),
+            6 => {
+                self.latch = value != 0.0;
+                if !self.latch {
+                    self.held.retain(|n| n.pressed);
+                    self.rebuild_pattern();
+                }
+            }
+            _ => console::error_1(&format!("Cannot update arpeggiator {}", param).into()),
+        }
+    }
+
+    pub fn note_on(&mut self, dto: &NoteDTO) {
+        // Avec le latch, un nouvel accord remplace le précédent une fois toutes les touches relâchées
+        if self.latch && !self.held.iter().any(|n| n.pressed) {
+            self.held.clear();
+        }
+
+        if self.held.is_empty() {
+            self.step_index = 0;
+            self.until_step = 0.0;
+        }
+
+        match self
+            .held
+            .iter_mut()
+            .find(|n| n.value == dto.value && n.channel == dto.channel)
+        {
+            Some(note) => {
+                note.velocity = dto.velocity;
+                note.pressed = true;
+            }
+            None => self.held.push(HeldNote {
+                value: dto.value,
+                velocity: dto.velocity,
+                channel: dto.channel,
+                pressed: true,
+            }),
+        }
+
+        self.rebuild_pattern();
+    }
+
+    /// Renvoie `false` si la note n'était pas tenue par l'arpégiateur
+    pub fn note_off(&mut self, dto: &NoteDTO) -> bool {
+        let Some(note) = self
+            .held
+            .iter_mut()
+            .find(|n| n.value == dto.value && n.channel == dto.channel)
+        else {
+            return false;
+        };
+
+        note.pressed = false;
+
+        if !self.latch {
+            self.held
+                .retain(|n| n.value != dto.value || n.channel != dto.channel);
+            self.rebuild_pattern();
+        }
+
+        true
+    }
+
+    fn rebuild_pattern(&mut self) {
+        self.pattern.clear();
+
+        let mut ordered = self.held.clone();
+        if self.mode != ArpMode::AsPlayed {
+            ordered.sort_by_key(|n| n.value);
+        }
+
+        for octave in 0..self.octaves {
+            for note in ordered.iter() {
+                let value = note.value as u32 + octave as u32 * 12;
+                if value <= 127 {
+                    self.pattern.push(HeldNote {
+                        value: value as u8,
+                        ..*note
+                    });
+                }
+            }
+        }
+
+        match self.mode {
+            ArpMode::Down => self.pattern.reverse(),
+            ArpMode::UpDown if self.pattern.len() > 2 => {
+                let descending: Vec<HeldNote> = self.pattern[1..self.pattern.len() - 1]
+                    .iter()
+                    .rev()
+                    .copied()
+                    .collect();
+                self.pattern.extend(descending);
+            }
+            _ => {}
+        }
+    }
+
+    fn step_length(&self) -> f64 {
+        let beat = TRANSPORT.with(|t| t.lock().unwrap().samples_per_beat());
+        (beat * self.rate as f64).max(1.0)
+    }
+
+    /// Durée du pas courant : les pas pairs s'allongent et les impairs raccourcissent
+    fn current_step_length(&self) -> f64 {
+        let swing = self.swing as f64 / 2.0;
+        let factor = if self.step_index.is_multiple_of(2) {
+            1.0 + swing
+        } else {
+            1.0 - swing
+        };
+
+        (self.step_length() * factor).max(1.0)
+    }
+
+    /// Nombre de frames qui peuvent être rendues avant le prochain événement
+    pub fn frames_until_next_event(&self) -> usize {
+        if !self.enabled {
+            return if self.playing.is_some() {
+                0
+            } else {
+                usize::MAX
+            };
+        }
+
+        let mut next = if self.pattern.is_empty() {
+            f64::MAX
+        } else {
+            self.until_step
+        };
+
+        if let Some((_, until_off)) = self.playing {
+            next = next.min(until_off);
+        }
+
+        if next == f64::MAX {
+            usize::MAX
+        } else {
+            next.ceil().max(0.0) as usize
+        }
+    }
+
+    /// Avance de `frames` échantillons et émet les événements arrivés à échéance
+    pub fn advance<F>(&mut self, frames: usize, mut emit: F)
+    where
+        F: FnMut(&NoteDTO),
+    {
+        if !self.enabled || self.pattern.is_empty() {
+            if let Some((note, _)) = self.playing.take() {
+                emit(&note.release());
+            }
+            return;
+        }
+
+        self.until_step -= frames as f64;
+
+        if let Some((note, until_off)) = self.playing.as_mut() {
+            *until_off -= frames as f64;
+            if *until_off <= 0.0 {
+                emit(&note.release());
+                self.playing = None;
+            }
+        }
+
+        if self.until_step > 0.0 {
+            return;
+        }
+
+        if let Some((note, _)) = self.playing.take() {
+            emit(&note.release());
+        }
+
+        let index = if self.mode == ArpMode::Random {
+            RANDOM.with(|r| r.borrow_mut().next_u32()) as usize % self.pattern.len()
+        } else {
+            self.step_index % self.pattern.len()
+        };
+        let note = self.pattern[index];
+
+        let length = self.current_step_length();
+        emit(&NoteDTO {
+            value: note.value,
+            velocity: note.velocity,
+            channel: note.channel,
+        });
+        self.playing = Some((note, (length * self.gate as f64).max(1.0)));
+
+        self.step_index = (self.step_index + 1) % (self.pattern.len() * 2);
+        self.until_step += length;
+    }
+}
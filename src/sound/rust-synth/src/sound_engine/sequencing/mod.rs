@@ -0,0 +1,3 @@
+pub mod arpeggiator;
+pub mod smf_player;
+pub mod step_sequencer;
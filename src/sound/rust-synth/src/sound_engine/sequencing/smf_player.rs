@@ -0,0 +1,302 @@
+use web_sys::console;
+
+use crate::{
+    global::TRANSPORT,
+    sound_engine::midi::smf::{MidiFile, SmfEventKind},
+    utils::types::NoteDTO,
+};
+
+/// Tolérance en temps pour comparer la position du transport aux dates des événements
+const BEAT_EPSILON: f64 = 1e-9;
+
+/// Convertit un tempo SMF (µs par noire) en bpm, borné comme dans le transport
+fn tempo_to_bpm(micros: u32) -> f32 {
+    (60_000_000.0 / micros as f32).clamp(20.0, 999.0)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PlayerEvent {
+    beat: f64,
+    track: usize,
+    kind: SmfEventKind,
+}
+
+/// Lecteur de fichier MIDI standard piloté par la position du transport.
+/// Toutes les pistes sont fusionnées en une liste datée en temps ; le processeur rend
+/// l'audio par blocs jusqu'au prochain événement pour un déclenchement à l'échantillon près.
+pub struct SmfPlayer {
+    events: Vec<PlayerEvent>,
+    muted: Vec<bool>,
+    /// Durée du morceau en temps
+    length: f64,
+    pub loop_enabled: bool,
+    loop_start: f64,
+    /// 0 = fin du morceau
+    loop_end: f64,
+    /// Le tempo et la signature du fichier pilotent le transport
+    follow_tempo: bool,
+
+    cursor: usize,
+    last_beats: f64,
+    /// Notes en cours (piste, canal, note), à relâcher lors d'un arrêt, d'un saut ou d'une coupure
+    sounding: Vec<(usize, u8, u8)>,
+    /// Un relâchement ou un repositionnement est demandé hors du rendu
+    pending: bool,
+}
+
+impl SmfPlayer {
+    pub fn new() -> Self {
+        Self {
+            events: Vec::new(),
+            muted: Vec::new(),
+            length: 0.0,
+            loop_enabled: false,
+            loop_start: 0.0,
+            loop_end: 0.0,
+            follow_tempo: true,
+            cursor: 0,
+            last_beats: 0.0,
+            sounding: Vec::new(),
+            pending: false,
+        }
+    }
+
+    pub fn load(&mut self, file: MidiFile) {
+        let ticks_per_quarter = file.ticks_per_quarter as f64;
+
+        self.events = file
+            .tracks
+            .iter()
+            .enumerate()
+            .flat_map(|(track, t)| {
+                t.events.iter().map(move |e| PlayerEvent {
+                    beat: e.tick as f64 / ticks_per_quarter,
+                    track,
+                    kind: e.kind,
+                })
+            })
+            .collect();
+
+        // À date égale, les relâchements passent avant les nouvelles notes
+        self.events.sort_by(|a, b| {
+            let a_on = matches!(a.kind, SmfEventKind::NoteOn { .. });
+            let b_on = matches!(b.kind, SmfEventKind::NoteOn { .. });
+            a.beat.total_cmp(&b.beat).then(a_on.cmp(&b_on))
+        });
+
+        self.length = self.events.last().map_or(0.0, |e| e.beat);
+        self.muted = vec![false; file.tracks.len()];
+        self.loop_start = 0.0;
+        self.loop_end = 0.0;
+        self.pending = true;
+    }
+
+    pub fn update(&mut self, param: u32, index: u32, value: f32) {
+        match param {
+            0 => self.loop_enabled = value != 0.0,
+            1 => self.loop_start = value.max(0.0) as f64,
+            2 => self.loop_end = value.max(0.0) as f64,
+            3 => {
+                if let Some(muted) = self.muted.get_mut(index as usize) {
+                    *muted = value != 0.0;
+                    self.pending = true;
+                }
+            }
+            4 => self.follow_tempo = value != 0.0,
+            5 => {
+                self.events.clear();
+                self.muted.clear();
+                self.length = 0.0;
+                self.pending = true;
+            }
+            _ => console::error_1(&format!("Cannot update midi file player {}", param).into()),
+        }
+    }
+
+    fn loop_end(&self) -> f64 {
+        if self.loop_end > 0.0 {
+            self.loop_end
+        } else {
+            self.length
+        }
+    }
+
+    fn is_looping(&self) -> bool {
+        self.loop_enabled && self.loop_end() > self.loop_start
+    }
+
+    /// Nombre de frames qui peuvent être rendues avant le prochain événement
+    pub fn frames_until_next_event(&self) -> usize {
+        if self.pending {
+            return 0;
+        }
+
+        let (playing, beats, samples_per_beat) = TRANSPORT.with(|t| {
+            let transport = t.lock().unwrap();
+            (
+                transport.playing,
+                transport.beats,
+                transport.samples_per_beat(),
+            )
+        });
+
+        if !playing {
+            return if self.sounding.is_empty() {
+                usize::MAX
+            } else {
+                0
+            };
+        }
+
+        let mut next = self.events.get(self.cursor).map_or(f64::MAX, |e| e.beat);
+        if self.is_looping() {
+            next = next.min(self.loop_end());
+        }
+
+        if next == f64::MAX {
+            return usize::MAX;
+        }
+
+        ((next - beats) * samples_per_beat).ceil().max(0.0) as usize
+    }
+
+    fn release<F>(&mut self, emit: &mut F, only_muted: bool)
+    where
+        F: FnMut(&NoteDTO),
+    {
+        let muted = &self.muted;
+        self.sounding.retain(|&(track, channel, value)| {
+            if only_muted && !muted.get(track).copied().unwrap_or(false) {
+                return true;
+            }
+            emit(&NoteDTO {
+                value,
+                velocity: 0,
+                channel,
+            });
+            false
+        });
+    }
+
+    /// Replace le curseur sur `beats` et rétablit le tempo et la signature en vigueur à cet endroit
+    fn seek(&mut self, beats: f64) {
+        self.cursor = self
+            .events
+            .partition_point(|e| e.beat < beats - BEAT_EPSILON);
+
+        if !self.follow_tempo {
+            return;
+        }
+
+        TRANSPORT.with(|t| {
+            let mut transport = t.lock().unwrap();
+            for event in self.events[..self.cursor].iter() {
+                match event.kind {
+                    SmfEventKind::Tempo(micros) => transport.bpm = tempo_to_bpm(micros),
+                    SmfEventKind::TimeSignature {
+                        numerator,
+                        denominator,
+                    } => {
+                        transport.numerator = numerator.max(1);
+                        transport.denominator = denominator;
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    /// Appelé après l'avancée du transport de `frames` échantillons : émet les événements
+    /// arrivés à échéance, gère la boucle et les sauts de position
+    pub fn advance<F>(&mut self, frames: usize, mut emit: F)
+    where
+        F: FnMut(&NoteDTO),
+    {
+        let (playing, mut beats, samples_per_beat) = TRANSPORT.with(|t| {
+            let transport = t.lock().unwrap();
+            (
+                transport.playing,
+                transport.beats,
+                transport.samples_per_beat(),
+            )
+        });
+
+        if self.pending {
+            self.pending = false;
+            self.release(&mut emit, true);
+            self.seek(beats);
+            self.last_beats = beats;
+        }
+
+        if !playing {
+            self.release(&mut emit, false);
+            self.last_beats = beats;
+            return;
+        }
+
+        // Le transport a été déplacé (démarrage, position MIDI, relocalisation)
+        let expected = self.last_beats + frames as f64 / samples_per_beat;
+        if (beats - expected).abs() > 1e-6 {
+            self.release(&mut emit, false);
+            self.seek(beats);
+        }
+
+        if self.is_looping() && beats >= self.loop_end() - BEAT_EPSILON {
+            self.release(&mut emit, false);
+            beats = self.loop_start + (beats - self.loop_end()).max(0.0);
+            TRANSPORT.with(|t| t.lock().unwrap().beats = beats);
+            self.seek(beats);
+        }
+
+        while let Some(event) = self.events.get(self.cursor).copied() {
+            if event.beat > beats + BEAT_EPSILON {
+                break;
+            }
+            self.cursor += 1;
+
+            match event.kind {
+                SmfEventKind::NoteOn {
+                    channel,
+                    note,
+                    velocity,
+                } => {
+                    if self.muted.get(event.track).copied().unwrap_or(false) {
+                        continue;
+                    }
+                    emit(&NoteDTO {
+                        value: note,
+                        velocity,
+                        channel,
+                    });
+                    self.sounding.push((event.track, channel, note));
+                }
+                SmfEventKind::NoteOff { channel, note } => {
+                    if let Some(pos) = self.sounding.iter().position(|&(track, c, value)| {
+                        track == event.track && c == channel && value == note
+                    }) {
+                        self.sounding.remove(pos);
+                        emit(&NoteDTO {
+                            value: note,
+                            velocity: 0,
+                            channel,
+                        });
+                    }
+                }
+                SmfEventKind::Tempo(micros) if self.follow_tempo => {
+                    TRANSPORT.with(|t| t.lock().unwrap().bpm = tempo_to_bpm(micros));
+                }
+                SmfEventKind::TimeSignature {
+                    numerator,
+                    denominator,
+                } if self.follow_tempo => TRANSPORT.with(|t| {
+                    let mut transport = t.lock().unwrap();
+                    transport.numerator = numerator.max(1);
+                    transport.denominator = denominator;
+                }),
+                _ => {}
+            }
+        }
+
+        self.last_beats = beats;
+    }
+}
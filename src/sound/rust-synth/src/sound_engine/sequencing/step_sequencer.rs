@@ -0,0 +1,274 @@
+use web_sys::console;
+
+use crate::{
+    global::{RANDOM, TRANSPORT},
+    sound_engine::synthetizer::part::Part,
+    utils::{
+        constants::{MAX_PATTERNS, MAX_STEPS},
+        types::NoteDTO,
+    },
+};
+
+/// Valeur imposée à un paramètre de sampler le temps d'un pas
+#[derive(Debug, Clone, Copy)]
+pub struct ParamLock {
+    pub sampler_id: u8,
+    pub key: u8,
+    pub value: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub active: bool,
+    pub note: u8,
+    pub velocity: u8,
+    /// Proportion du pas pendant laquelle la note est tenue
+    pub gate: f32,
+    /// Probabilité de déclenchement, entre 0 et 1
+    pub probability: f32,
+    pub locks: Vec<ParamLock>,
+}
+
+impl Default for Step {
+    fn default() -> Self {
+        Self {
+            active: false,
+            note: 60,
+            velocity: 100,
+            gate: 0.5,
+            probability: 1.0,
+            locks: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    steps: Vec<Step>,
+    length: usize,
+}
+
+impl Pattern {
+    fn new() -> Self {
+        Self {
+            steps: vec![Step::default(); MAX_STEPS],
+            length: 16,
+        }
+    }
+}
+
+/// Séquenceur pas à pas cadencé par l'horloge échantillon du processeur.
+/// Démarré et arrêté par les événements de transport de la file MIDI.
+pub struct StepSequencer {
+    patterns: Vec<Pattern>,
+    current_pattern: usize,
+    /// Motif demandé, pris en compte au début du cycle suivant
+    next_pattern: usize,
+    /// Durée d'un pas en temps (0.25 = double croche)
+    rate: f32,
+    /// Canal MIDI des notes émises, pour le routage vers les parties
+    channel: u8,
+    playing: bool,
+
+    step_index: usize,
+    /// Échantillons restants avant le prochain pas
+    until_step: f64,
+    /// Note en cours et échantillons restants avant son relâchement
+    playing_note: Option<(NoteDTO, f64)>,
+    /// Valeurs d'origine des paramètres verrouillés par le pas en cours
+    restore: Vec<ParamLock>,
+}
+
+impl StepSequencer {
+    pub fn new() -> Self {
+        Self {
+            patterns: vec![Pattern::new(); MAX_PATTERNS],
+            current_pattern: 0,
+            next_pattern: 0,
+            rate: 0.25,
+            channel: 0,
+            playing: false,
+            step_index: 0,
+            until_step: 0.0,
+            playing_note: None,
+            restore: Vec::new(),
+        }
+    }
+
+    /// `index` désigne un pas par `motif * MAX_STEPS + pas` ; pour les verrous
+    /// (`param` >= 100, clé de sampler = `param - 100`), l'id du sampler occupe les bits 16 et plus
+    pub fn update(&mut self, param: u32, index: u32, value: f32) {
+        match param {
+            0 => self.rate = value.max(1.0 / 64.0),
+            1 => self.next_pattern = (value as usize).min(MAX_PATTERNS - 1),
+            2 => {
+                if let Some(pattern) = self.patterns.get_mut(index as usize) {
+                    pattern.length = (value as usize).clamp(1, MAX_STEPS);
+                }
+            }
+            3..=8 => {
+                let Some(step) = self.step_mut(index) else {
+                    return;
+                };
+                match param {
+                    3 => step.active = value != 0.0,
+                    4 => step.note = value.clamp(0.0, 127.0) as u8,
+                    5 => step.velocity = value.clamp(1.0, 127.0) as u8,
+                    6 => step.gate = value.clamp(0.01, 1.0),
+                    7 => step.probability = value.clamp(0.0, 1.0),
+                    _ => step.locks.clear(),
+                }
+            }
+            9 => self.channel = value as u8 & 0x0F,
+            100..=355 => {
+                let sampler_id = (index >> 16) as u8;
+                let key = (param - 100) as u8;
+                let Some(step) = self.step_mut(index & 0xFFFF) else {
+                    return;
+                };
+                step.locks
+                    .retain(|l| l.sampler_id != sampler_id || l.key != key);
+                step.locks.push(ParamLock {
+                    sampler_id,
+                    key,
+                    value,
+                });
+            }
+            _ => console::error_1(&format!("Cannot update step sequencer {}", param).into()),
+        }
+    }
+
+    fn step_mut(&mut self, address: u32) -> Option<&mut Step> {
+        let address = address as usize;
+        self.patterns
+            .get_mut(address / MAX_STEPS)
+            .map(|pattern| &mut pattern.steps[address % MAX_STEPS])
+    }
+
+    /// Démarre en se calant sur la position du transport
+    pub fn start(&mut self, beats: f64) {
+        let steps = beats / self.rate as f64;
+        let length = self.patterns[self.next_pattern].length;
+
+        self.playing = true;
+        self.current_pattern = self.next_pattern;
+        self.step_index = steps.ceil() as usize % length;
+        self.until_step = (steps.ceil() - steps) * self.step_length();
+    }
+
+    pub fn stop(&mut self) {
+        self.playing = false;
+    }
+
+    fn step_length(&self) -> f64 {
+        let beat = TRANSPORT.with(|t| t.lock().unwrap().samples_per_beat());
+        (beat * self.rate as f64).max(1.0)
+    }
+
+    /// Nombre de frames qui peuvent être rendues avant le prochain événement
+    pub fn frames_until_next_event(&self) -> usize {
+        if !self.playing {
+            let pending = self.playing_note.is_some() || !self.restore.is_empty();
+            return if pending { 0 } else { usize::MAX };
+        }
+
+        let mut next = self.until_step;
+        if let Some((_, until_off)) = self.playing_note {
+            next = next.min(until_off);
+        }
+
+        next.ceil().max(0.0) as usize
+    }
+
+    fn release<F>(&mut self, parts: &mut [Part], emit: &mut F)
+    where
+        F: FnMut(&NoteDTO, &mut [Part]),
+    {
+        if let Some((note, _)) = self.playing_note.take() {
+            emit(
+                &NoteDTO {
+                    velocity: 0,
+                    ..note
+                },
+                parts,
+            );
+        }
+
+        for lock in self.restore.drain(..) {
+            if let Some(sampler) = Part::find_sampler_mut(parts, lock.sampler_id) {
+                sampler.update_param(lock.key, lock.value);
+            }
+        }
+    }
+
+    /// Avance de `frames` échantillons. Les verrous d'un pas sont appliqués avant sa note
+    /// pour qu'elle démarre avec, et restent en place jusqu'au pas suivant.
+    pub fn advance<F>(&mut self, frames: usize, parts: &mut [Part], mut emit: F)
+    where
+        F: FnMut(&NoteDTO, &mut [Part]),
+    {
+        if !self.playing {
+            self.release(parts, &mut emit);
+            return;
+        }
+
+        self.until_step -= frames as f64;
+
+        if let Some((note, until_off)) = self.playing_note.as_mut() {
+            *until_off -= frames as f64;
+            if *until_off <= 0.0 {
+                emit(
+                    &NoteDTO {
+                        velocity: 0,
+                        ..*note
+                    },
+                    parts,
+                );
+                self.playing_note = None;
+            }
+        }
+
+        if self.until_step > 0.0 {
+            return;
+        }
+
+        self.release(parts, &mut emit);
+
+        if self.step_index == 0 {
+            self.current_pattern = self.next_pattern;
+        }
+
+        let length = self.step_length();
+        let pattern = &self.patterns[self.current_pattern];
+        let step = &pattern.steps[self.step_index];
+
+        let triggered =
+            step.active && RANDOM.with(|r| r.borrow_mut().next_f32()) < step.probability;
+
+        if triggered {
+            for lock in step.locks.iter() {
+                let Some(sampler) = Part::find_sampler_mut(parts, lock.sampler_id) else {
+                    continue;
+                };
+                if let Some(previous) = sampler.get_param(lock.key) {
+                    self.restore.push(ParamLock {
+                        value: previous,
+                        ..*lock
+                    });
+                    sampler.update_param(lock.key, lock.value);
+                }
+            }
+
+            let note = NoteDTO {
+                value: step.note,
+                velocity: step.velocity,
+                channel: self.channel,
+            };
+            emit(&note, parts);
+            self.playing_note = Some((note, (length * step.gate as f64).max(1.0)));
+        }
+
+        self.step_index = (self.step_index + 1) % pattern.length;
+        self.until_step += length;
+    }
+}
@@ -0,0 +1,111 @@
+use web_sys::console;
+
+/// Sortie vers laquelle un pad est envoyé
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PadOutput {
+    /// Bus principal, passe par la chaîne d'effets
+    Main = 0,
+    /// Sortie directe, sans effets
+    Direct = 1,
+}
+
+impl TryFrom<u8> for PadOutput {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PadOutput::Main),
+            1 => Ok(PadOutput::Direct),
+            _ => Err("Sortie de pad inconnue"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DrumPad {
+    pub sample_id: u32,
+    pub gain: f32,
+    pub pan: f32,
+    /// Transposition en demi-tons par rapport à la hauteur d'origine du sample
+    pub tune: f32,
+    pub output: PadOutput,
+    /// 0 = aucun groupe ; un pad coupe les autres pads de son groupe
+    pub choke_group: u8,
+}
+
+impl Default for DrumPad {
+    fn default() -> Self {
+        Self {
+            sample_id: 0,
+            gain: 1.0,
+            pan: 0.0,
+            tune: 0.0,
+            output: PadOutput::Main,
+            choke_group: 0,
+        }
+    }
+}
+
+impl DrumPad {
+    pub fn gain_l(&self) -> f32 {
+        (1.0 - self.pan) / 2.0
+    }
+
+    pub fn gain_r(&self) -> f32 {
+        (1.0 + self.pan) / 2.0
+    }
+
+    pub fn step(&self) -> f32 {
+        2.0f32.powf(self.tune / 12.0)
+    }
+}
+
+/// Kit de batterie : quand il est actif, chaque touche déclenche son pad au lieu des samplers
+pub struct DrumKit {
+    pub enabled: bool,
+    pads: [Option<DrumPad>; 128],
+}
+
+impl DrumKit {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            pads: [None; 128],
+        }
+    }
+
+    pub fn pad(&self, key: u8) -> Option<DrumPad> {
+        self.pads.get(key as usize).copied().flatten()
+    }
+
+    pub fn update(&mut self, param: u32, key: u32, value: f32) {
+        if param == 0 {
+            self.enabled = value != 0.0;
+            return;
+        }
+
+        let Some(slot) = self.pads.get_mut(key as usize) else {
+            return;
+        };
+
+        if param == 7 {
+            *slot = None;
+            return;
+        }
+
+        let pad = slot.get_or_insert_with(DrumPad::default);
+        match param {
+            1 => pad.sample_id = value as u32,
+            2 => pad.gain = value.max(0.0),
+            3 => pad.pan = value.clamp(-1.0, 1.0),
+            4 => pad.tune = value,
+            5 => {
+                if let Ok(output) = PadOutput::try_from(value as u8) {
+                    pad.output = output
+                }
+            }
+            6 => pad.choke_group = value as u8,
+            _ => console::error_1(&format!("Cannot update drum pad {}", param).into()),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+/// Expression reçue sur un canal MIDI. En MPE chaque note a son propre canal,
+/// l'expression est donc propre à la note.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Expression {
+    /// En demi-tons
+    pub pitch_bend: f32,
+    /// Aftertouch de canal, entre 0 et 1
+    pub pressure: f32,
+    /// CC74, entre 0 et 1
+    pub timbre: f32,
+    /// Accord fin et grossier du canal (RPN 1 et 2), en demi-tons
+    pub fine_tuning: f32,
+    pub coarse_tuning: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExpressionKind {
+    PitchBend,
+    Pressure,
+    Timbre,
+    FineTuning,
+    CoarseTuning,
+}
+
+/// Expression d'un canal et celle du canal maître de sa zone MPE, qui s'y ajoute
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChannelExpression {
+    pub note: Expression,
+    pub zone: Expression,
+}
+
+impl ChannelExpression {
+    pub fn set(&mut self, kind: ExpressionKind, value: f32, master: bool) {
+        let expression = if master {
+            &mut self.zone
+        } else {
+            &mut self.note
+        };
+
+        match kind {
+            ExpressionKind::PitchBend => expression.pitch_bend = value,
+            ExpressionKind::Pressure => expression.pressure = value,
+            ExpressionKind::Timbre => expression.timbre = value,
+            ExpressionKind::FineTuning => expression.fine_tuning = value,
+            ExpressionKind::CoarseTuning => expression.coarse_tuning = value,
+        }
+    }
+
+    pub fn pitch_bend(&self) -> f32 {
+        self.note.pitch_bend + self.zone.pitch_bend
+    }
+
+    /// Accord du canal, en demi-tons
+    pub fn tuning(&self) -> f32 {
+        self.note.fine_tuning
+            + self.note.coarse_tuning
+            + self.zone.fine_tuning
+            + self.zone.coarse_tuning
+    }
+
+    pub fn pressure(&self) -> f32 {
+        (self.note.pressure + self.zone.pressure).min(1.0)
+    }
+
+    pub fn timbre(&self) -> f32 {
+        (self.note.timbre + self.zone.timbre).min(1.0)
+    }
+}
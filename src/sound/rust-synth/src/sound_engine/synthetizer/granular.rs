@@ -0,0 +1,146 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    global::{RANDOM, SAMPLE_MANAGER},
+    sound_engine::synthetizer::sampler::Sampler,
+    utils::constants::{MAX_GRAINS, SAMPLE_RATE},
+};
+
+/// Enveloppe appliquée à chaque grain
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GrainWindow {
+    Hann = 0,
+    Triangle = 1,
+    Trapezoid = 2,
+    Gaussian = 3,
+}
+
+impl TryFrom<u8> for GrainWindow {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(GrainWindow::Hann),
+            1 => Ok(GrainWindow::Triangle),
+            2 => Ok(GrainWindow::Trapezoid),
+            3 => Ok(GrainWindow::Gaussian),
+            _ => Err("Fenêtre de grain inconnue"),
+        }
+    }
+}
+
+impl GrainWindow {
+    /// `x` est la progression du grain entre 0 et 1
+    pub fn gain(&self, x: f32) -> f32 {
+        match self {
+            GrainWindow::Hann => 0.5 - 0.5 * (std::f32::consts::TAU * x).cos(),
+            GrainWindow::Triangle => 1.0 - (2.0 * x - 1.0).abs(),
+            GrainWindow::Trapezoid => (x.min(1.0 - x) / 0.1).min(1.0),
+            GrainWindow::Gaussian => (-0.5 * ((x - 0.5) / 0.15).powi(2)).exp(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Grain {
+    pub position: f32,
+    pub step: f32,
+    pub age: u32,
+    pub length: u32,
+    pub gain_l: f32,
+    pub gain_r: f32,
+}
+
+/// Nuage de grains d'une voix, lus dans le sample du sampler
+#[derive(Debug, Clone)]
+pub struct GrainCloud {
+    pub grains: Vec<Grain>,
+    pub spawn_clock: f32,
+}
+
+impl GrainCloud {
+    pub fn new() -> Self {
+        Self {
+            grains: Vec::new(),
+            // Le premier grain part dès le début de la note
+            spawn_clock: 1.0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.grains.clear();
+        self.spawn_clock = 1.0;
+    }
+
+    pub fn render(&mut self, sampler: &Sampler, freq: f32) -> (f32, f32) {
+        SAMPLE_MANAGER.with(|sm| {
+            let sm = sm.lock().unwrap();
+            let Some(sample) = sm.get_sample(sampler.sample_id) else {
+                return (0.0, 0.0);
+            };
+            if sample.values.is_empty() {
+                return (0.0, 0.0);
+            }
+
+            self.spawn_clock += sampler.grain_density / SAMPLE_RATE;
+            while self.spawn_clock >= 1.0 {
+                self.spawn_clock -= 1.0;
+                self.spawn(sampler, sample.values.len(), freq / sample.base_frequency());
+            }
+
+            let mut sum_l = 0.0;
+            let mut sum_r = 0.0;
+
+            for grain in self.grains.iter_mut() {
+                let window = sampler
+                    .grain_window
+                    .gain(grain.age as f32 / grain.length as f32);
+                let value = sample.read_interpolated(grain.position) * window;
+
+                sum_l += value * grain.gain_l;
+                sum_r += value * grain.gain_r;
+
+                grain.position += grain.step;
+                grain.age += 1;
+            }
+
+            self.grains.retain(|g| g.age < g.length);
+
+            // Nombre moyen de grains superposés, pour garder un niveau stable
+            let overlap = sampler.grain_density * sampler.grain_size as f32 / SAMPLE_RATE;
+            let normalization = 1.0 / overlap.max(1.0).sqrt();
+
+            (sum_l * normalization, sum_r * normalization)
+        })
+    }
+
+    fn spawn(&mut self, sampler: &Sampler, table_len: usize, base_step: f32) {
+        if self.grains.len() >= MAX_GRAINS {
+            return;
+        }
+        if self.grains.capacity() == 0 {
+            self.grains.reserve(MAX_GRAINS);
+        }
+
+        let (position_random, pitch_random, pan_random) = RANDOM.with(|r| {
+            let mut r = r.borrow_mut();
+            (r.next_bipolar(), r.next_bipolar(), r.next_bipolar())
+        });
+
+        let position = (sampler.grain_position + sampler.grain_position_jitter * position_random)
+            .rem_euclid(1.0)
+            * table_len as f32;
+        let step = base_step * 2.0f32.powf(sampler.grain_pitch_jitter * pitch_random / 12.0);
+        let pan = sampler.grain_stereo_scatter * pan_random;
+
+        self.grains.push(Grain {
+            position,
+            step,
+            age: 0,
+            length: sampler.grain_size.max(1),
+            gain_l: 1.0 - pan,
+            gain_r: 1.0 + pan,
+        });
+    }
+}
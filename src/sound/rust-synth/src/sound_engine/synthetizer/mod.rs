@@ -1,4 +1,9 @@
+pub mod drum_kit;
+pub mod expression;
+pub mod granular;
 pub mod note;
 pub mod note_manager;
+pub mod part;
 pub mod sample_manager;
 pub mod sampler;
+pub mod time_stretch;
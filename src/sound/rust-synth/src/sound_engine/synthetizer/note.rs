@@ -1,28 +1,70 @@
-use crate::sound_engine::synthetizer::sampler::Sampler;
+use crate::{
+    global::SAMPLE_MANAGER,
+    sound_engine::synthetizer::{
+        drum_kit::DrumPad,
+        expression::ChannelExpression,
+        granular::GrainCloud,
+        sampler::{ModulationMode, Sampler},
+        time_stretch::TimeStretcher,
+    },
+    utils::{constants::CHOKE_FADE_MS, toolkit::ToolKit},
+};
 
 #[derive(Debug, Clone)]
 pub struct NoteOscState {
     pub current_phase: f32,
+    pub start_phase: f32,
     pub start_sample_index: u64,
     pub end_sample_index: u64,
     pub finished: bool,
+    pub modulation_phase: f32,
+    pub grain_cloud: GrainCloud,
+    pub time_stretcher: TimeStretcher,
 }
 
 impl NoteOscState {
     pub fn new(phase_shift: f32) -> Self {
         Self {
             current_phase: phase_shift % 1.0,
+            start_phase: phase_shift % 1.0,
             start_sample_index: 0,
             end_sample_index: 0,
             finished: false,
+            modulation_phase: 0.0,
+            grain_cloud: GrainCloud::new(),
+            time_stretcher: TimeStretcher::new(),
         }
     }
 
     pub fn reset(&mut self, phase_shift: f32) {
         self.current_phase = phase_shift % 1.0;
+        self.start_phase = phase_shift % 1.0;
         self.start_sample_index = 0;
         self.end_sample_index = 0;
         self.finished = false;
+        self.modulation_phase = 0.0;
+        self.grain_cloud.reset();
+        self.time_stretcher.reset();
+    }
+}
+
+/// Signaux reçus par un sampler de la part des modulateurs de la même voix
+#[derive(Debug, Clone, Copy)]
+pub struct ModulationInput {
+    pub phase: f32,
+    pub frequency: f32,
+    pub ring: f32,
+    pub ring_connected: bool,
+}
+
+impl Default for ModulationInput {
+    fn default() -> Self {
+        Self {
+            phase: 0.0,
+            frequency: 0.0,
+            ring: 1.0,
+            ring_connected: false,
+        }
     }
 }
 
@@ -30,49 +72,98 @@ impl NoteOscState {
 pub struct Note {
     pub value: u8,
     pub velocity: u8,
+    /// Canal MIDI, qui porte l'expression de la note en MPE
+    pub channel: u8,
+    /// Fréquence selon l'accordage, lue une fois au déclenchement
+    pub frequency: f32,
     pub has_ended: bool,
     pub to_remove: bool,
     pub start_sample_index: u64,
     pub end_sample_index: u64,
     pub current_phase: f32,
-    pub osc_states: Vec<NoteOscState>,
+    /// Pour chaque sampler, un état par voix d'unisson
+    pub osc_states: Vec<Vec<NoteOscState>>,
+    pub modulation_inputs: Vec<ModulationInput>,
+    /// Pad joué à la place des samplers en mode batterie
+    pub pad: Option<DrumPad>,
+    pub choked: bool,
 }
 
 impl Note {
-    pub fn new(value: u8, velocity: u8, samplers: &[Sampler]) -> Self {
+    pub fn new(value: u8, velocity: u8, channel: u8, samplers: &[Sampler]) -> Self {
         let osc_states = samplers
             .iter()
-            .map(|osc| NoteOscState::new(osc.phase_shift))
+            .map(|osc| osc.create_voice_states())
             .collect();
 
         Note {
             value,
             velocity,
+            channel,
+            frequency: ToolKit::midi_to_freq(value),
             has_ended: false,
             to_remove: false,
             start_sample_index: 0,
             end_sample_index: 0,
             current_phase: 0.0,
             osc_states,
+            modulation_inputs: vec![ModulationInput::default(); samplers.len()],
+            pad: None,
+            choked: false,
+        }
+    }
+
+    pub fn new_pad(value: u8, velocity: u8, channel: u8, pad: DrumPad) -> Self {
+        Note {
+            value,
+            velocity,
+            channel,
+            frequency: ToolKit::midi_to_freq(value),
+            has_ended: false,
+            to_remove: false,
+            start_sample_index: 0,
+            end_sample_index: 0,
+            current_phase: 0.0,
+            osc_states: vec![vec![NoteOscState::new(0.0)]],
+            modulation_inputs: Vec::new(),
+            pad: Some(pad),
+            choked: false,
         }
     }
 
+    /// Coupe la note avec un court fondu, pour les groupes d'étouffement
+    pub fn choke(&mut self) {
+        self.choked = true;
+    }
+
     pub fn restart(&mut self, samplers: &[Sampler]) {
+        self.frequency = ToolKit::midi_to_freq(self.value);
         self.has_ended = false;
         self.end_sample_index = 0;
         self.start_sample_index = 0;
 
-        // Réajuster si le nombre d'samplers a changé
-        if self.osc_states.len() != samplers.len() {
+        // Réajuster si le nombre d'samplers ou de voix d'unisson a changé
+        let layout_changed = self.osc_states.len() != samplers.len()
+            || self
+                .osc_states
+                .iter()
+                .zip(samplers.iter())
+                .any(|(states, osc)| states.len() != osc.unison_voices.max(1) as usize);
+
+        if layout_changed {
             self.osc_states = samplers
                 .iter()
-                .map(|osc| NoteOscState::new(osc.phase_shift))
+                .map(|osc| osc.create_voice_states())
                 .collect();
         } else {
-            for (state, osc) in self.osc_states.iter_mut().zip(samplers.iter()) {
-                state.reset(osc.phase_shift);
+            for (states, osc) in self.osc_states.iter_mut().zip(samplers.iter()) {
+                for state in states.iter_mut() {
+                    state.reset(osc.voice_start_phase());
+                }
             }
         }
+
+        self.modulation_inputs = vec![ModulationInput::default(); samplers.len()];
     }
 
     pub fn end_note(&mut self) {
@@ -80,23 +171,114 @@ impl Note {
     }
 
     pub fn is_finished(&self) -> bool {
-        self.osc_states.iter().all(|s| s.finished)
+        self.osc_states.iter().flatten().all(|s| s.finished)
     }
 
-    pub fn generate_samples_of_all_samplers(&mut self, samplers: &[Sampler]) -> (f32, f32) {
+    /// Lecture unique du pad : le relâchement de la touche est ignoré,
+    /// seul l'étouffement interrompt le son
+    pub fn generate_pad_sample(&mut self) -> (f32, f32) {
+        let (Some(pad), Some(state)) = (
+            self.pad,
+            self.osc_states.first_mut().and_then(|s| s.first_mut()),
+        ) else {
+            return (0.0, 0.0);
+        };
+
+        if state.finished {
+            return (0.0, 0.0);
+        }
+
+        let position = state.start_sample_index as f32 * pad.step();
+        let Some(mut value) = SAMPLE_MANAGER.with(|sm| {
+            sm.lock()
+                .unwrap()
+                .get_one_shot_value(pad.sample_id, position)
+        }) else {
+            state.finished = true;
+            return (0.0, 0.0);
+        };
+
+        value *= self.velocity as f32 * pad.gain / 127.0;
+
+        if self.choked {
+            let fade_length = ToolKit::convert_ms_to_sample(CHOKE_FADE_MS) as u64;
+            if state.end_sample_index >= fade_length {
+                state.finished = true;
+                return (0.0, 0.0);
+            }
+            value *= 1.0 - state.end_sample_index as f32 / fade_length as f32;
+            state.end_sample_index += 1;
+        }
+
+        state.start_sample_index += 1;
+
+        (value * pad.gain_l(), value * pad.gain_r())
+    }
+
+    /// Les samplers sont rendus dans l'ordre : un modulateur dont la cible le précède
+    /// (ou qui se cible lui-même) n'agit qu'à l'échantillon suivant, ce qui donne du feedback.
+    pub fn generate_samples_of_all_samplers(
+        &mut self,
+        samplers: &[Sampler],
+        expression: &ChannelExpression,
+    ) -> (f32, f32) {
         if self.to_remove {
             return (0.0, 0.0);
         }
 
+        if self.modulation_inputs.len() != samplers.len() {
+            self.modulation_inputs
+                .resize(samplers.len(), ModulationInput::default());
+        }
+
         let mut note_sum_l = 0.0;
         let mut note_sum_r = 0.0;
 
         for (osc_index, sampler) in samplers.iter().enumerate() {
-            if let Some(state) = self.osc_states.get_mut(osc_index) {
-                let (l, r) =
-                    sampler.generate_sample(self.value, self.velocity, state, self.has_ended);
-                note_sum_l += l;
-                note_sum_r += r;
+            let input = std::mem::take(&mut self.modulation_inputs[osc_index]);
+
+            if let Some(states) = self.osc_states.get_mut(osc_index) {
+                let routing = sampler.expression_routing(expression);
+                let (mut l, mut r) = sampler.generate_voices(
+                    self.value,
+                    self.frequency * routing.pitch_ratio,
+                    self.velocity,
+                    states,
+                    self.has_ended,
+                    &input,
+                );
+                l *= routing.gain;
+                r *= routing.gain;
+
+                if input.ring_connected {
+                    l *= input.ring;
+                    r *= input.ring;
+                }
+
+                match sampler.modulation_target_index(samplers) {
+                    Some(target_index) => {
+                        let target = &mut self.modulation_inputs[target_index];
+                        let index = sampler.modulation_index + routing.modulation_index;
+                        let value = (l + r) * 0.5;
+
+                        match sampler.modulation_mode {
+                            ModulationMode::Phase => {
+                                target.phase += value * index / std::f32::consts::TAU
+                            }
+                            ModulationMode::Frequency => target.frequency += value * index,
+                            ModulationMode::Ring => {
+                                let depth = index.clamp(0.0, 1.0);
+                                target.ring *= 1.0 - depth + depth * value;
+                                target.ring_connected = true;
+                            }
+                            ModulationMode::None => {}
+                        }
+                    }
+                    None => {
+                        note_sum_l += l * sampler.gain_l;
+                        note_sum_r += r * sampler.gain_r;
+                    }
+                }
             }
         }
 
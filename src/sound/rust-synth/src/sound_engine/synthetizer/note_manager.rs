@@ -1,39 +1,100 @@
 use web_sys::console;
 
 use crate::{
-    global::MIXER,
-    sound_engine::synthetizer::{note::Note, sampler::Sampler},
+    global::{MIXER, TUNING},
+    sound_engine::synthetizer::{
+        drum_kit::{DrumKit, PadOutput},
+        expression::{ChannelExpression, ExpressionKind},
+        note::Note,
+        sampler::Sampler,
+    },
     utils::types::NoteDTO,
 };
 
 pub struct NoteManager {
     notes: Vec<Note>,
+    pub drum_kit: DrumKit,
+    /// Dernière expression reçue par canal, une note la prend dès son déclenchement
+    expression: [ChannelExpression; 16],
 }
 
 impl NoteManager {
     pub fn new() -> Self {
-        Self { notes: Vec::new() }
+        Self {
+            notes: Vec::new(),
+            drum_kit: DrumKit::new(),
+            expression: [ChannelExpression::default(); 16],
+        }
+    }
+
+    /// Une vélocité nulle vaut relâchement
+    pub fn process_event(&mut self, dto: &NoteDTO, samplers: &[Sampler]) {
+        if dto.velocity > 0 {
+            self.add_note(dto, samplers);
+        } else {
+            self.end_note(dto);
+        }
     }
 
     pub fn add_note(&mut self, dto: &NoteDTO, samplers: &[Sampler]) {
-        if let Some(existing_note) = self.notes.iter_mut().find(|n| n.value == dto.value) {
+        if self.drum_kit.enabled {
+            self.trigger_pad(dto);
+            return;
+        }
+
+        if !TUNING.with(|t| t.lock().unwrap().is_mapped(dto.value)) {
+            return;
+        }
+
+        if let Some(existing_note) = self
+            .notes
+            .iter_mut()
+            .find(|n| n.value == dto.value && n.channel == dto.channel)
+        {
             if existing_note.has_ended {
                 existing_note.restart(samplers);
             }
         } else {
             self.notes
-                .push(Note::new(dto.value, dto.velocity, samplers));
+                .push(Note::new(dto.value, dto.velocity, dto.channel, samplers));
+        }
+    }
+
+    /// Un pad se redéclenche à chaque frappe et étouffe les pads de son groupe,
+    /// y compris sa frappe précédente
+    fn trigger_pad(&mut self, dto: &NoteDTO) {
+        let Some(pad) = self.drum_kit.pad(dto.value) else {
+            return;
+        };
+
+        for note in self.notes.iter_mut() {
+            let same_group =
+                pad.choke_group != 0 && note.pad.is_some_and(|p| p.choke_group == pad.choke_group);
+
+            if note.value == dto.value || same_group {
+                note.choke();
+            }
         }
+
+        self.notes
+            .push(Note::new_pad(dto.value, dto.velocity, dto.channel, pad));
     }
 
     pub fn end_note(&mut self, dto: &NoteDTO) {
         for note in self.notes.iter_mut() {
-            if note.value == dto.value && !note.has_ended {
+            if note.value == dto.value && note.channel == dto.channel && !note.has_ended {
                 note.end_note();
             }
         }
     }
 
+    /// `master` : message du canal maître d'une zone MPE, recopié sur chacun de ses canaux
+    pub fn set_expression(&mut self, channel: u8, kind: ExpressionKind, value: f32, master: bool) {
+        if let Some(expression) = self.expression.get_mut(channel as usize) {
+            expression.set(kind, value, master);
+        }
+    }
+
     pub fn cleanup_finished_notes(&mut self) {
         self.notes.retain(|note| {
             let finished = note.is_finished();
@@ -42,15 +103,18 @@ impl NoteManager {
         });
     }
 
+    /// `direct_buffer` reçoit les pads envoyés en sortie directe, sans effets
     pub fn generate_raw_samples(
         &mut self,
         output_buffer: &mut [f32],
+        direct_buffer: &mut [f32],
         frame_count: usize, // C'est le nombre de frames stéréo
         samplers: &[Sampler],
     ) {
         output_buffer.fill(0.0);
+        direct_buffer.fill(0.0);
 
-        if output_buffer.len() < frame_count * 2 {
+        if output_buffer.len() < frame_count * 2 || direct_buffer.len() < frame_count * 2 {
             console::error_1(
                 &format!(
                     "Output buffer in generate_raw_samples is too small for {} frames!",
@@ -61,21 +125,44 @@ impl NoteManager {
             return;
         }
 
+        // Normalisation par nombre d'samplers (les pads n'en dépendent pas)
+        let osc_count = samplers.len().max(1) as f32;
+
         for i in 0..frame_count {
             let mut mixed_l = 0.0;
             let mut mixed_r = 0.0;
+            let mut direct_l = 0.0;
+            let mut direct_r = 0.0;
 
             if self.notes.is_empty() {
             } else {
                 for note in self.notes.iter_mut() {
-                    let (l, r) = note.generate_samples_of_all_samplers(samplers);
-                    mixed_l += l;
-                    mixed_r += r;
+                    match note.pad {
+                        Some(pad) => {
+                            let (l, r) = note.generate_pad_sample();
+                            if pad.output == PadOutput::Direct {
+                                direct_l += l;
+                                direct_r += r;
+                            } else {
+                                mixed_l += l;
+                                mixed_r += r;
+                            }
+                        }
+                        None => {
+                            let expression = &self.expression[note.channel as usize & 0x0F];
+                            let (l, r) =
+                                note.generate_samples_of_all_samplers(samplers, expression);
+                            mixed_l += l / osc_count;
+                            mixed_r += r / osc_count;
+                        }
+                    }
                 }
             }
 
             output_buffer[i * 2] = mixed_l;
             output_buffer[i * 2 + 1] = mixed_r;
+            direct_buffer[i * 2] = direct_l;
+            direct_buffer[i * 2 + 1] = direct_r;
 
             continue;
         }
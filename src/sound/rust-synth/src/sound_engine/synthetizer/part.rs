@@ -0,0 +1,131 @@
+use web_sys::console;
+
+use crate::{
+    sound_engine::{
+        dsp::mixer::Mixer,
+        synthetizer::{expression::ExpressionKind, note_manager::NoteManager, sampler::Sampler},
+    },
+    utils::{constants::PROCESSING_BUFFER_SIZE, types::NoteDTO},
+};
+
+/// Partie d'un jeu multitimbral : ses propres samplers, notes et chaîne d'effets,
+/// jouée par un canal MIDI et une plage de touches (partage ou superposition du clavier).
+pub struct Part {
+    pub note_manager: NoteManager,
+    pub samplers: Vec<Sampler>,
+    pub mixer: Mixer,
+    /// Canal écouté, `None` pour tous les canaux
+    pub channel: Option<u8>,
+    pub key_low: u8,
+    pub key_high: u8,
+    pub gain: f32,
+    pub enabled: bool,
+    buffer: Vec<f32>,
+    direct_buffer: Vec<f32>,
+}
+
+impl Part {
+    pub fn new() -> Self {
+        Self {
+            note_manager: NoteManager::new(),
+            samplers: Vec::new(),
+            mixer: Mixer::new(),
+            channel: None,
+            key_low: 0,
+            key_high: 127,
+            gain: 1.0,
+            enabled: true,
+            buffer: vec![0.0; PROCESSING_BUFFER_SIZE * 2],
+            direct_buffer: vec![0.0; PROCESSING_BUFFER_SIZE * 2],
+        }
+    }
+
+    pub fn listens_to(&self, channel: u8) -> bool {
+        self.enabled && self.channel.is_none_or(|c| c == channel)
+    }
+
+    pub fn accepts(&self, dto: &NoteDTO) -> bool {
+        self.listens_to(dto.channel) && (self.key_low..=self.key_high).contains(&dto.value)
+    }
+
+    /// Envoie une note aux parties concernées. Un relâchement ignore la plage de touches
+    /// pour ne pas laisser de note bloquée si la plage a changé entre-temps.
+    pub fn dispatch(parts: &mut [Part], dto: &NoteDTO) {
+        for part in parts.iter_mut() {
+            let routed = if dto.velocity > 0 {
+                part.accepts(dto)
+            } else {
+                part.listens_to(dto.channel)
+            };
+
+            if routed {
+                part.note_manager.process_event(dto, &part.samplers);
+            }
+        }
+    }
+
+    /// L'expression est conservée par canal dans toutes les parties, y compris avant
+    /// la note qu'elle accompagne
+    pub fn set_expression(
+        parts: &mut [Part],
+        channel: u8,
+        kind: ExpressionKind,
+        value: f32,
+        master: bool,
+    ) {
+        for part in parts.iter_mut() {
+            part.note_manager
+                .set_expression(channel, kind, value, master);
+        }
+    }
+
+    /// Les identifiants de samplers sont uniques sur l'ensemble des parties
+    pub fn find_sampler_mut(parts: &mut [Part], id: u8) -> Option<&mut Sampler> {
+        parts
+            .iter_mut()
+            .flat_map(|p| p.samplers.iter_mut())
+            .find(|s| s.id == id)
+    }
+
+    pub fn update(&mut self, param: u32, value: f32) {
+        match param {
+            2 => {
+                self.channel = if value < 0.0 {
+                    None
+                } else {
+                    Some(value as u8 & 0x0F)
+                }
+            }
+            3 => self.key_low = value.clamp(0.0, 127.0) as u8,
+            4 => self.key_high = value.clamp(0.0, 127.0) as u8,
+            5 => self.gain = value.max(0.0),
+            6 => self.enabled = value != 0.0,
+            _ => console::error_1(&format!("Cannot update part {}", param).into()),
+        }
+    }
+
+    /// Rend `frame_count` frames à partir de la frame `offset` du bloc
+    pub fn render(&mut self, offset: usize, frame_count: usize) {
+        let range = offset * 2..(offset + frame_count) * 2;
+        self.note_manager.generate_raw_samples(
+            &mut self.buffer[range.clone()],
+            &mut self.direct_buffer[range],
+            frame_count,
+            &self.samplers,
+        );
+    }
+
+    /// Passe le bloc rendu dans la chaîne d'effets de la partie et l'ajoute aux bus maîtres
+    pub fn mix_into(&mut self, master: &mut [f32], direct: &mut [f32]) {
+        for i in (0..master.len()).step_by(2) {
+            let mut l = self.buffer[i];
+            let mut r = self.buffer[i + 1];
+            self.mixer.render(&mut l, &mut r);
+
+            master[i] += l * self.gain;
+            master[i + 1] += r * self.gain;
+            direct[i] += self.direct_buffer[i] * self.gain;
+            direct[i + 1] += self.direct_buffer[i + 1] * self.gain;
+        }
+    }
+}
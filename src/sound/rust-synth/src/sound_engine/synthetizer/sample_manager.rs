@@ -1,7 +1,11 @@
 use js_sys::Float32Array;
 use web_sys::console;
 
-use crate::utils::{toolkit::ToolKit, types::Sample};
+use crate::utils::{
+    constants::{SAMPLE_RATE, TRANSIENT_FRAME_SIZE, TRANSIENT_MIN_SPACING_MS},
+    toolkit::ToolKit,
+    types::Sample,
+};
 
 pub struct SampleManager {
     pub samples: Vec<Sample>,
@@ -14,7 +18,14 @@ impl SampleManager {
         }
     }
 
-    pub fn add_sample(&mut self, id: u32, raw_values: Float32Array, length: u32, hq: u8) {
+    pub fn add_sample(
+        &mut self,
+        id: u32,
+        raw_values: Float32Array,
+        length: u32,
+        channels: u8,
+        hq: u8,
+    ) {
         // Récupérer uniquement la portion utile du Float32Array
         console::log_1(&"Création d'un sample".into());
         let useful_slice = raw_values.subarray(0, length);
@@ -28,32 +39,117 @@ impl SampleManager {
         let sample = Sample {
             id,
             values: boxed_values,
+            channels,
             hq: hq,
+            slices: Vec::new(),
         };
         self.samples.push(sample);
     }
 
-    pub fn get_value(&self, sample_id: u32, index: u64, frequency: f32) -> f32 {
-        if let Some(sample) = self.samples.iter().find(|s| s.id == sample_id) {
-            let table = &sample.values;
-            if table.is_empty() {
-                return 0.0;
+    pub fn get_sample(&self, sample_id: u32) -> Option<&Sample> {
+        self.samples.iter().find(|s| s.id == sample_id)
+    }
+
+    fn get_sample_mut(&mut self, sample_id: u32) -> Option<&mut Sample> {
+        self.samples.iter_mut().find(|s| s.id == sample_id)
+    }
+
+    pub fn update(&mut self, param: u32, sample_id: u32, value: f32) {
+        match param {
+            0 => self.add_slice(sample_id, value as u32),
+            1 => self.clear_slices(sample_id),
+            2 => self.detect_transients(sample_id, value),
+            _ => console::error_1(&format!("Cannot update samples {}", param).into()),
+        }
+    }
+
+    pub fn add_slice(&mut self, sample_id: u32, position: u32) {
+        if let Some(sample) = self.get_sample_mut(sample_id) {
+            if position as usize >= sample.channel_length() {
+                return;
+            }
+            if let Err(index) = sample.slices.binary_search(&position) {
+                sample.slices.insert(index, position);
             }
-            let table_len = table.len() as f32;
-            let base_frequency: f32 = if sample.hq == 0 {
-                ToolKit::midi_to_freq(60) // C4
-            } else {
-                ToolKit::midi_to_freq(12) // C0
-            };
+        }
+    }
+
+    pub fn clear_slices(&mut self, sample_id: u32) {
+        if let Some(sample) = self.get_sample_mut(sample_id) {
+            sample.slices.clear();
+        }
+    }
+
+    /// Remplace les marqueurs par les attaques détectées. On compare l'énergie de chaque
+    /// trame à la moyenne des trames précédentes ; `sensitivity` (0 à 1) abaisse le seuil.
+    pub fn detect_transients(&mut self, sample_id: u32, sensitivity: f32) {
+        let Some(sample) = self.get_sample_mut(sample_id) else {
+            return;
+        };
+
+        let threshold = 4.0 - 2.8 * sensitivity.clamp(0.0, 1.0);
+        let hop = TRANSIENT_FRAME_SIZE / 2;
+        let min_spacing = ToolKit::convert_ms_to_sample(TRANSIENT_MIN_SPACING_MS);
+
+        // Seul le canal gauche est analysé : les positions restent dans la partie jouable
+        let mut frames = sample.values[..sample.channel_length()]
+            .chunks(hop)
+            .map(|frame| frame.iter().map(|v| v * v).sum::<f32>() / frame.len() as f32);
+
+        let mut slices = vec![0];
+        let mut history = frames.next().unwrap_or(0.0);
+
+        for (frame_index, energy) in frames.enumerate() {
+            let position = ((frame_index + 1) * hop) as u32;
+            let last = *slices.last().unwrap_or(&0);
+
+            if energy > history * threshold
+                && energy > 1e-5
+                && (position - last) as usize >= min_spacing
+            {
+                slices.push(position);
+            }
+
+            // Moyenne glissante sur environ huit trames
+            history += (energy - history) * 0.125;
+        }
+
+        sample.slices = slices;
+    }
+
+    /// Lecture unique : `None` une fois la fin du sample atteinte
+    pub fn get_one_shot_value(&self, sample_id: u32, position: f32) -> Option<f32> {
+        let sample = self.get_sample(sample_id)?;
+
+        if position >= sample.values.len() as f32 {
+            return None;
+        }
+
+        Some(sample.read_interpolated(position))
+    }
+
+    /// Valeur de la tranche `slice_index` à `offset` échantillons de son début,
+    /// `None` une fois la tranche terminée
+    pub fn get_slice_value(&self, sample_id: u32, slice_index: usize, offset: f32) -> Option<f32> {
+        let sample = self.get_sample(sample_id)?;
+        let (start, end) = sample.slice_bounds(slice_index)?;
+        let position = start as f32 + offset;
+
+        if position >= end as f32 {
+            return None;
+        }
+
+        Some(sample.read_interpolated(position))
+    }
+
+    /// `phase_offset` est exprimé en cycles de la fréquence de base du sample
+    pub fn get_value(&self, sample_id: u32, index: u64, frequency: f32, phase_offset: f32) -> f32 {
+        if let Some(sample) = self.get_sample(sample_id) {
+            let base_frequency = sample.base_frequency();
             let step = frequency / base_frequency; // combien de cycles par index ?
-            let pos_in_table = (index as f32 * step) % table_len;
-            let i0 = pos_in_table.floor() as usize;
-            let i1 = (i0 + 1) % table.len();
-
-            let frac = pos_in_table - i0 as f32;
-            let v0 = table[i0];
-            let v1 = table[i1];
-            (v0 * (1.0 - frac)) + (v1 * frac)
+            let offset = phase_offset * SAMPLE_RATE / base_frequency;
+
+            sample.read_interpolated(index as f32 * step + offset)
         } else {
             0.0
         }
@@ -1,11 +1,109 @@
 use wasm_bindgen::prelude::*;
 
 use crate::{
-    global::SAMPLE_MANAGER,
-    sound_engine::synthetizer::note::NoteOscState,
-    utils::{constants::SAMPLE_RATE, toolkit::ToolKit},
+    global::{RANDOM, SAMPLE_MANAGER, TRANSPORT},
+    sound_engine::synthetizer::{
+        expression::ChannelExpression,
+        granular::GrainWindow,
+        note::{ModulationInput, NoteOscState},
+    },
+    utils::{
+        constants::{MAX_UNISON_VOICES, SAMPLE_RATE},
+        toolkit::ToolKit,
+    },
 };
 
+/// Façon dont un sampler agit sur sa cible lorsqu'il est utilisé comme opérateur
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModulationMode {
+    /// Le sampler est une porteuse : il est envoyé vers la sortie
+    None = 0,
+    /// Modulation de phase (le "FM" des synthés type DX7)
+    Phase = 1,
+    /// Modulation de fréquence vraie (la déviation est intégrée dans la phase)
+    Frequency = 2,
+    /// Modulation en anneau : la sortie de la cible est multipliée par celle du modulateur
+    Ring = 3,
+}
+
+impl TryFrom<u8> for ModulationMode {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ModulationMode::None),
+            1 => Ok(ModulationMode::Phase),
+            2 => Ok(ModulationMode::Frequency),
+            3 => Ok(ModulationMode::Ring),
+            _ => Err("Mode de modulation inconnu"),
+        }
+    }
+}
+
+/// Destination de la pression ou du timbre (CC74) d'une note
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExpressionTarget {
+    None = 0,
+    /// Le volume suit l'expression, `amount` est la profondeur entre 0 et 1
+    Gain = 1,
+    /// Transposition de `amount` demi-tons à fond
+    Pitch = 2,
+    /// S'ajoute à l'index de modulation du sampler utilisé comme opérateur
+    ModulationIndex = 3,
+}
+
+impl TryFrom<u8> for ExpressionTarget {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ExpressionTarget::None),
+            1 => Ok(ExpressionTarget::Gain),
+            2 => Ok(ExpressionTarget::Pitch),
+            3 => Ok(ExpressionTarget::ModulationIndex),
+            _ => Err("Destination d'expression inconnue"),
+        }
+    }
+}
+
+/// Effet de l'expression d'une note sur un sampler
+#[derive(Debug, Clone, Copy)]
+pub struct ExpressionRouting {
+    pub pitch_ratio: f32,
+    pub gain: f32,
+    pub modulation_index: f32,
+}
+
+/// Manière dont le sample est lu
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaybackMode {
+    /// Lecture transposée classique, vitesse et hauteur liées
+    Pitched = 0,
+    /// Nuage de grains prélevés autour d'une position du sample
+    Granular = 1,
+    /// Hauteur suivant la note mais vitesse de lecture indépendante (WSOLA)
+    TimeStretch = 2,
+    /// Une tranche du sample par touche, jouée une fois à sa hauteur d'origine
+    Sliced = 3,
+}
+
+impl TryFrom<u8> for PlaybackMode {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PlaybackMode::Pitched),
+            1 => Ok(PlaybackMode::Granular),
+            2 => Ok(PlaybackMode::TimeStretch),
+            3 => Ok(PlaybackMode::Sliced),
+            _ => Err("Mode de lecture inconnu"),
+        }
+    }
+}
+
 #[wasm_bindgen]
 #[derive(Debug, Clone, Copy)]
 pub struct Sampler {
@@ -21,9 +119,231 @@ pub struct Sampler {
     pub gain: f32,
     pub gain_l: f32,
     pub gain_r: f32,
+    pub modulation_mode: ModulationMode,
+    pub modulation_target: u8,
+    pub modulation_index: f32,
+    pub unison_voices: u8,
+    pub unison_detune: f32,
+    pub unison_detune_curve: f32,
+    pub unison_spread: f32,
+    pub unison_phase_randomness: f32,
+    pub playback_mode: PlaybackMode,
+    pub grain_size: u32,
+    pub grain_density: f32,
+    pub grain_position: f32,
+    pub grain_position_jitter: f32,
+    pub grain_pitch_jitter: f32,
+    pub grain_window: GrainWindow,
+    pub grain_stereo_scatter: f32,
+    pub stretch_rate: f32,
+    pub stretch_source_bpm: f32,
+    pub stretch_target_bpm: f32,
+    pub slice_root_key: u8,
+    pub pressure_target: ExpressionTarget,
+    pub pressure_amount: f32,
+    pub timbre_target: ExpressionTarget,
+    pub timbre_amount: f32,
 }
 
 impl Sampler {
+    pub fn new(id: u8) -> Self {
+        Self {
+            id,
+            sample_id: 0,
+            attack_length: ToolKit::convert_ms_to_sample(0.0) as u64,
+            decay_length: ToolKit::convert_ms_to_sample(10.0) as u64,
+            sustain_gain: 0.5,
+            release_length: ToolKit::convert_ms_to_sample(500.0) as u64,
+            frequency_shift: 1.0,
+            delay_length: ToolKit::convert_ms_to_sample(0.0) as u64,
+            phase_shift: 0.0,
+            gain: 0.5,
+            gain_l: 1.0,
+            gain_r: 1.0,
+            modulation_mode: ModulationMode::None,
+            modulation_target: 0,
+            modulation_index: 1.0,
+            unison_voices: 1,
+            unison_detune: 20.0,
+            unison_detune_curve: 1.0,
+            unison_spread: 0.5,
+            unison_phase_randomness: 0.0,
+            playback_mode: PlaybackMode::Pitched,
+            grain_size: ToolKit::convert_ms_to_sample(80.0) as u32,
+            grain_density: 20.0,
+            grain_position: 0.0,
+            grain_position_jitter: 0.05,
+            grain_pitch_jitter: 0.0,
+            grain_window: GrainWindow::Hann,
+            grain_stereo_scatter: 0.3,
+            stretch_rate: 1.0,
+            stretch_source_bpm: 0.0,
+            stretch_target_bpm: 0.0,
+            slice_root_key: 36,
+            pressure_target: ExpressionTarget::None,
+            pressure_amount: 1.0,
+            timbre_target: ExpressionTarget::None,
+            timbre_amount: 1.0,
+        }
+    }
+
+    /// Applique un paramètre reçu depuis la file des samplers (clé `OscKey` côté JS)
+    pub fn update_param(&mut self, key: u8, value: f32) {
+        match key {
+            1 => self.attack_length = value as u64,
+            2 => self.release_length = value as u64,
+            3 => self.decay_length = value as u64,
+            4 => self.sustain_gain = value * 0.1,
+            5 => self.gain = value * 0.1,
+            6 => self.delay_length = value as u64,
+            7 => self.frequency_shift = value,
+            8 => self.phase_shift = value,
+            9 => self.sample_id = value as u32,
+            10 => {
+                self.gain_l = (1.0 - value) / 2.0;
+                self.gain_r = (1.0 + value) / 2.0
+            }
+            11 => {
+                if let Ok(mode) = ModulationMode::try_from(value as u8) {
+                    self.modulation_mode = mode
+                }
+            }
+            12 => self.modulation_target = value as u8,
+            13 => self.modulation_index = value,
+            14 => self.set_unison_voices(value as u8),
+            15 => self.unison_detune = value.max(0.0),
+            16 => self.unison_detune_curve = value,
+            17 => self.unison_spread = value.clamp(0.0, 1.0),
+            18 => self.unison_phase_randomness = value.clamp(0.0, 1.0),
+            19 => {
+                if let Ok(mode) = PlaybackMode::try_from(value as u8) {
+                    self.playback_mode = mode
+                }
+            }
+            20 => self.grain_size = value as u32,
+            21 => self.grain_density = value.max(0.0),
+            22 => self.grain_position = value.clamp(0.0, 1.0),
+            23 => self.grain_position_jitter = value.clamp(0.0, 1.0),
+            24 => self.grain_pitch_jitter = value.max(0.0),
+            25 => {
+                if let Ok(window) = GrainWindow::try_from(value as u8) {
+                    self.grain_window = window
+                }
+            }
+            26 => self.grain_stereo_scatter = value.clamp(0.0, 1.0),
+            27 => self.stretch_rate = value.max(0.0),
+            28 => self.stretch_source_bpm = value.max(0.0),
+            29 => self.stretch_target_bpm = value.max(0.0),
+            30 => self.slice_root_key = value as u8,
+            31 => {
+                if let Ok(target) = ExpressionTarget::try_from(value as u8) {
+                    self.pressure_target = target
+                }
+            }
+            32 => self.pressure_amount = value,
+            33 => {
+                if let Ok(target) = ExpressionTarget::try_from(value as u8) {
+                    self.timbre_target = target
+                }
+            }
+            34 => self.timbre_amount = value,
+            _ => {}
+        }
+    }
+
+    /// Valeur courante d'un paramètre, dans les unités attendues par `update_param`
+    pub fn get_param(&self, key: u8) -> Option<f32> {
+        let value = match key {
+            1 => self.attack_length as f32,
+            2 => self.release_length as f32,
+            3 => self.decay_length as f32,
+            4 => self.sustain_gain / 0.1,
+            5 => self.gain / 0.1,
+            6 => self.delay_length as f32,
+            7 => self.frequency_shift,
+            8 => self.phase_shift,
+            9 => self.sample_id as f32,
+            10 => self.gain_r - self.gain_l,
+            11 => self.modulation_mode as u8 as f32,
+            12 => self.modulation_target as f32,
+            13 => self.modulation_index,
+            14 => self.unison_voices as f32,
+            15 => self.unison_detune,
+            16 => self.unison_detune_curve,
+            17 => self.unison_spread,
+            18 => self.unison_phase_randomness,
+            19 => self.playback_mode as u8 as f32,
+            20 => self.grain_size as f32,
+            21 => self.grain_density,
+            22 => self.grain_position,
+            23 => self.grain_position_jitter,
+            24 => self.grain_pitch_jitter,
+            25 => self.grain_window as u8 as f32,
+            26 => self.grain_stereo_scatter,
+            27 => self.stretch_rate,
+            28 => self.stretch_source_bpm,
+            29 => self.stretch_target_bpm,
+            30 => self.slice_root_key as f32,
+            31 => self.pressure_target as u8 as f32,
+            32 => self.pressure_amount,
+            33 => self.timbre_target as u8 as f32,
+            34 => self.timbre_amount,
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
+    /// Vitesse de lecture en mode time-stretch : le rapport des tempos si le tempo d'origine
+    /// de la boucle est connu (le tempo cible par défaut est celui du transport),
+    /// sinon la vitesse libre
+    pub fn stretch_speed(&self) -> f32 {
+        if self.stretch_source_bpm <= 0.0 {
+            return self.stretch_rate;
+        }
+
+        let target_bpm = if self.stretch_target_bpm > 0.0 {
+            self.stretch_target_bpm
+        } else {
+            TRANSPORT.with(|t| t.lock().unwrap().bpm)
+        };
+
+        target_bpm / self.stretch_source_bpm
+    }
+
+    pub fn set_unison_voices(&mut self, voices: u8) {
+        self.unison_voices = voices.clamp(1, MAX_UNISON_VOICES);
+    }
+
+    /// Phase de départ d'une voix : le phase shift du sampler plus une part aléatoire
+    pub fn voice_start_phase(&self) -> f32 {
+        let random = RANDOM.with(|r| r.borrow_mut().next_f32());
+        (self.phase_shift + random * self.unison_phase_randomness) % 1.0
+    }
+
+    /// Un état par voix d'unisson, chacune avec sa propre phase de départ
+    pub fn create_voice_states(&self) -> Vec<NoteOscState> {
+        (0..self.unison_voices.max(1))
+            .map(|_| NoteOscState::new(self.voice_start_phase()))
+            .collect()
+    }
+
+    /// Position de la voix dans l'unisson, de -1 (la plus grave, à gauche) à 1
+    fn unison_position(voice_index: usize, voice_count: usize) -> f32 {
+        if voice_count <= 1 {
+            return 0.0;
+        }
+
+        voice_index as f32 * 2.0 / (voice_count - 1) as f32 - 1.0
+    }
+
+    /// Rapport de fréquence d'une voix. La courbe (exposant) resserre les voix autour
+    /// du centre quand elle est > 1 et les repousse vers les extrêmes quand elle est < 1.
+    fn unison_detune_ratio(&self, position: f32) -> f32 {
+        let shaped = position.signum() * position.abs().powf(self.unison_detune_curve.max(0.01));
+        2.0f32.powf(shaped * self.unison_detune / 1200.0)
+    }
+
     pub fn apply_adsr(&self, state: &mut NoteOscState, note_has_ended: bool, value: &mut f32) {
         if note_has_ended {
             if state.end_sample_index >= self.release_length + self.delay_length {
@@ -55,28 +375,146 @@ impl Sampler {
         }
     }
 
-    pub fn generate_sample(
+    /// Index dans `samplers` du sampler modulé par celui-ci, s'il agit comme modulateur
+    pub fn modulation_target_index(&self, samplers: &[Sampler]) -> Option<usize> {
+        if self.modulation_mode == ModulationMode::None {
+            return None;
+        }
+
+        samplers.iter().position(|s| s.id == self.modulation_target)
+    }
+
+    /// Combine le pitch bend et l'accord du canal de la note avec les routages de sa
+    /// pression et de son timbre
+    pub fn expression_routing(&self, expression: &ChannelExpression) -> ExpressionRouting {
+        let mut semitones = expression.pitch_bend() + expression.tuning();
+        let mut routing = ExpressionRouting {
+            pitch_ratio: 1.0,
+            gain: 1.0,
+            modulation_index: 0.0,
+        };
+
+        let sources = [
+            (
+                self.pressure_target,
+                self.pressure_amount,
+                expression.pressure(),
+            ),
+            (self.timbre_target, self.timbre_amount, expression.timbre()),
+        ];
+        for (target, amount, value) in sources {
+            match target {
+                ExpressionTarget::None => {}
+                ExpressionTarget::Gain => {
+                    let depth = amount.clamp(0.0, 1.0);
+                    routing.gain *= 1.0 - depth + depth * value;
+                }
+                ExpressionTarget::Pitch => semitones += amount * value,
+                ExpressionTarget::ModulationIndex => routing.modulation_index += amount * value,
+            }
+        }
+
+        if semitones != 0.0 {
+            routing.pitch_ratio = 2.0f32.powf(semitones / 12.0);
+        }
+
+        routing
+    }
+
+    /// Génère toutes les voix d'unisson du sampler pour une note, avant panoramique.
+    /// `frequency` est celle de la note selon l'accordage, déjà transposée
+    /// (pitch bend et expression).
+    pub fn generate_voices(
         &self,
         note_value: u8,
+        frequency: f32,
+        note_velocity: u8,
+        states: &mut [NoteOscState],
+        note_has_ended: bool,
+        input: &ModulationInput,
+    ) -> (f32, f32) {
+        let freq: f32 = frequency * self.frequency_shift;
+        let voice_count = states.len();
+
+        // Les tranches sont jouées en entier, le relâchement de la touche est ignoré
+        let note_has_ended = note_has_ended && self.playback_mode != PlaybackMode::Sliced;
+
+        let mut sum_l = 0.0;
+        let mut sum_r = 0.0;
+
+        for (voice_index, state) in states.iter_mut().enumerate() {
+            let position = Sampler::unison_position(voice_index, voice_count);
+            let voice_freq = freq * self.unison_detune_ratio(position);
+            let (l, r) = self.generate_value(
+                note_value,
+                voice_freq,
+                note_velocity,
+                state,
+                note_has_ended,
+                input,
+            );
+
+            // Panoramique à puissance constante, ramené à un gain unité au centre
+            let angle = (position * self.unison_spread + 1.0) * std::f32::consts::FRAC_PI_4;
+            sum_l += l * angle.cos() * std::f32::consts::SQRT_2;
+            sum_r += r * angle.sin() * std::f32::consts::SQRT_2;
+        }
+
+        // Normalisation en puissance pour que le volume ne grimpe pas avec le nombre de voix
+        let normalization = 1.0 / (voice_count.max(1) as f32).sqrt();
+
+        (sum_l * normalization, sum_r * normalization)
+    }
+
+    /// Génère la valeur stéréo (enveloppe comprise) d'une voix.
+    /// Les entrées de modulation sont un décalage de phase en cycles et une déviation
+    /// relative de fréquence, fournis par les modulateurs de la note. Elles ne s'appliquent
+    /// qu'à la lecture transposée.
+    fn generate_value(
+        &self,
+        note_value: u8,
+        freq: f32,
         note_velocity: u8,
         state: &mut NoteOscState,
         note_has_ended: bool,
+        input: &ModulationInput,
     ) -> (f32, f32) {
         if state.finished {
             return (0.0, 0.0);
         }
 
-        let freq: f32 = ToolKit::midi_to_freq(note_value) * self.frequency_shift;
+        let (l, r) = match self.playback_mode {
+            PlaybackMode::Pitched => {
+                // La déviation de fréquence est intégrée pour rester continue en phase
+                state.modulation_phase += freq * input.frequency / SAMPLE_RATE;
+                state.modulation_phase %= 1.0;
 
-        let mut value = SAMPLE_MANAGER.with(|sm| {
-            sm.lock()
-                .unwrap()
-                .get_value(self.sample_id, state.start_sample_index, freq)
-        }) * note_velocity as f32
-            * self.gain
-            / 127.0;
+                let value = SAMPLE_MANAGER.with(|sm| {
+                    sm.lock().unwrap().get_value(
+                        self.sample_id,
+                        state.start_sample_index,
+                        freq,
+                        state.start_phase + input.phase + state.modulation_phase,
+                    )
+                });
+                (value, value)
+            }
+            PlaybackMode::Granular => state.grain_cloud.render(self, freq),
+            PlaybackMode::TimeStretch => {
+                let value = state.time_stretcher.render(self, freq);
+                (value, value)
+            }
+            PlaybackMode::Sliced => match self.render_slice(note_value, state) {
+                Some(value) => (value, value),
+                None => {
+                    state.finished = true;
+                    return (0.0, 0.0);
+                }
+            },
+        };
 
-        self.apply_adsr(state, note_has_ended, &mut value);
+        let mut envelope = note_velocity as f32 * self.gain / 127.0;
+        self.apply_adsr(state, note_has_ended, &mut envelope);
 
         // Mise à jour de l'état
         state.current_phase += freq / SAMPLE_RATE;
@@ -87,10 +525,19 @@ impl Sampler {
             state.end_sample_index += 1;
         }
 
-        let left = value * self.gain_l;
-        let right = value * self.gain_r;
+        (l * envelope, r * envelope)
+    }
+
+    /// Les touches consécutives à partir de `slice_root_key` jouent les tranches successives
+    fn render_slice(&self, note_value: u8, state: &NoteOscState) -> Option<f32> {
+        let slice_index = note_value.checked_sub(self.slice_root_key)? as usize;
+        let offset = state.start_sample_index as f32 * self.frequency_shift;
 
-        (left, right)
+        SAMPLE_MANAGER.with(|sm| {
+            sm.lock()
+                .unwrap()
+                .get_slice_value(self.sample_id, slice_index, offset)
+        })
     }
 
     pub fn change_sample(&mut self, sample_id: u32) {
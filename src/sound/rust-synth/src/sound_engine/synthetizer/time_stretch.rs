@@ -0,0 +1,155 @@
+use crate::{
+    global::SAMPLE_MANAGER,
+    sound_engine::synthetizer::{granular::GrainWindow, sampler::Sampler},
+    utils::{
+        constants::{STRETCH_GRAIN_SIZE, STRETCH_SEARCH_TOLERANCE},
+        types::Sample,
+    },
+};
+
+const HOP_SIZE: u32 = STRETCH_GRAIN_SIZE / 2;
+const CORRELATION_LENGTH: usize = 256;
+
+#[derive(Debug, Clone, Copy)]
+struct StretchGrain {
+    start: f32,
+    age: u32,
+    active: bool,
+}
+
+/// Lecture WSOLA : des grains fenêtrés (Hann, recouvrement de 50 %) sont lus à la vitesse
+/// de la note pendant que la position d'analyse avance au tempo voulu. Chaque nouveau grain
+/// est recalé autour de sa position nominale pour ressembler au prolongement naturel du
+/// grain précédent, ce qui évite les phases qui s'annulent.
+#[derive(Debug, Clone)]
+pub struct TimeStretcher {
+    analysis_position: f32,
+    grains: [StretchGrain; 2],
+    last_grain: Option<usize>,
+    hop_counter: u32,
+}
+
+impl TimeStretcher {
+    pub fn new() -> Self {
+        Self {
+            analysis_position: 0.0,
+            grains: [StretchGrain {
+                start: 0.0,
+                age: 0,
+                active: false,
+            }; 2],
+            last_grain: None,
+            hop_counter: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = TimeStretcher::new();
+    }
+
+    pub fn render(&mut self, sampler: &Sampler, freq: f32) -> f32 {
+        SAMPLE_MANAGER.with(|sm| {
+            let sm = sm.lock().unwrap();
+            let Some(sample) = sm.get_sample(sampler.sample_id) else {
+                return 0.0;
+            };
+            if sample.values.is_empty() {
+                return 0.0;
+            }
+
+            let step = freq / sample.base_frequency();
+
+            if self.hop_counter == 0 {
+                self.start_grain(sample, step);
+                self.hop_counter = HOP_SIZE;
+            }
+            self.hop_counter -= 1;
+
+            let mut value = 0.0;
+            for grain in self.grains.iter_mut().filter(|g| g.active) {
+                let window = GrainWindow::Hann.gain(grain.age as f32 / STRETCH_GRAIN_SIZE as f32);
+                value += window * sample.read_interpolated(grain.start + grain.age as f32 * step);
+
+                grain.age += 1;
+                if grain.age >= STRETCH_GRAIN_SIZE {
+                    grain.active = false;
+                }
+            }
+
+            self.analysis_position = (self.analysis_position + sampler.stretch_speed())
+                .rem_euclid(sample.values.len() as f32);
+
+            value
+        })
+    }
+
+    fn start_grain(&mut self, sample: &Sample, step: f32) {
+        let nominal = self.analysis_position;
+
+        let start = match self.last_grain {
+            Some(last) => {
+                let natural = self.grains[last].start + HOP_SIZE as f32 * step;
+                nominal + TimeStretcher::best_offset(sample, natural, nominal, step)
+            }
+            None => nominal,
+        };
+
+        let slot = match self.last_grain {
+            Some(last) => 1 - last,
+            None => 0,
+        };
+
+        self.grains[slot] = StretchGrain {
+            start,
+            age: 0,
+            active: true,
+        };
+        self.last_grain = Some(slot);
+    }
+
+    /// Décalage autour de `nominal` dont le contenu ressemble le plus à `natural`.
+    /// Recherche grossière par pas de 8 puis affinage à l'échantillon près. Les pas,
+    /// l'étendue et la fenêtre sont comptés en échantillons de sortie, donc multipliés
+    /// par `step` dans le sample puisque les grains y sont lus à cette vitesse.
+    fn best_offset(sample: &Sample, natural: f32, nominal: f32, step: f32) -> f32 {
+        let tolerance = STRETCH_SEARCH_TOLERANCE as i32;
+
+        let mut best = 0;
+        let mut best_score = f32::MIN;
+        for offset in (-tolerance..=tolerance).step_by(8) {
+            let candidate = nominal + offset as f32 * step;
+            let score = TimeStretcher::similarity(sample, natural, candidate, step);
+            if score > best_score {
+                best_score = score;
+                best = offset;
+            }
+        }
+
+        let coarse = best;
+        for offset in (coarse - 7)..=(coarse + 7) {
+            let candidate = nominal + offset as f32 * step;
+            let score = TimeStretcher::similarity(sample, natural, candidate, step);
+            if score > best_score {
+                best_score = score;
+                best = offset;
+            }
+        }
+
+        best as f32 * step
+    }
+
+    /// Corrélation normalisée par l'énergie du candidat, sous-échantillonnée d'un facteur 4
+    fn similarity(sample: &Sample, reference: f32, candidate: f32, step: f32) -> f32 {
+        let mut correlation = 0.0;
+        let mut energy = 1e-9;
+
+        for k in (0..CORRELATION_LENGTH).step_by(4) {
+            let a = sample.read_interpolated(reference + k as f32 * step);
+            let b = sample.read_interpolated(candidate + k as f32 * step);
+            correlation += a * b;
+            energy += b * b;
+        }
+
+        correlation / energy.sqrt()
+    }
+}
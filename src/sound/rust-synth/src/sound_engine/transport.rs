@@ -0,0 +1,81 @@
+use js_sys::Float32Array;
+use web_sys::console;
+
+use crate::utils::constants::{REPORT_TRANSPORT_INDEX, SAMPLE_RATE};
+
+/// Horloge musicale du moteur : lecture, position en temps (noires), tempo et signature.
+/// Elle avance avec l'horloge échantillon du processeur et sert de référence
+/// aux fonctions synchronisées (séquenceur, arpégiateur, délais).
+pub struct Transport {
+    pub playing: bool,
+    pub bpm: f32,
+    pub numerator: u8,
+    pub denominator: u8,
+    /// Position en noires depuis le début du morceau
+    pub beats: f64,
+}
+
+impl Transport {
+    pub fn new() -> Self {
+        Self {
+            playing: false,
+            bpm: 120.0,
+            numerator: 4,
+            denominator: 4,
+            beats: 0.0,
+        }
+    }
+
+    pub fn samples_per_beat(&self) -> f64 {
+        60.0 / self.bpm as f64 * SAMPLE_RATE as f64
+    }
+
+    /// Durée d'une mesure en noires
+    pub fn beats_per_bar(&self) -> f64 {
+        self.numerator as f64 * 4.0 / self.denominator as f64
+    }
+
+    pub fn advance(&mut self, frames: usize) {
+        if self.playing {
+            self.beats += frames as f64 / self.samples_per_beat();
+        }
+    }
+
+    /// La lecture se contrôle depuis l'`EventHandler` qui synchronise aussi le séquenceur
+    pub fn update(&mut self, param: u32, value: f32) {
+        match param {
+            1 => self.bpm = value.clamp(20.0, 999.0),
+            2 => self.numerator = (value as u8).max(1),
+            3 => {
+                let denominator = value as u8;
+                if denominator.is_power_of_two() && denominator <= 32 {
+                    self.denominator = denominator;
+                }
+            }
+            4 => self.beats = value.max(0.0) as f64,
+            _ => console::error_1(&format!("Cannot update transport {}", param).into()),
+        }
+    }
+
+    /// Publie l'état pour l'affichage : lecture, tempo, position, mesure et temps (à partir de 1),
+    /// signature
+    pub fn write_report(&self, report: &Float32Array) {
+        let beats_per_bar = self.beats_per_bar();
+        let bar = (self.beats / beats_per_bar).floor();
+        let beat_in_bar = (self.beats - bar * beats_per_bar) * self.denominator as f64 / 4.0;
+
+        let values = [
+            self.playing as u8 as f32,
+            self.bpm,
+            self.beats as f32,
+            bar as f32 + 1.0,
+            beat_in_bar.floor() as f32 + 1.0,
+            self.numerator as f32,
+            self.denominator as f32,
+        ];
+
+        for (i, value) in values.iter().enumerate() {
+            report.set_index(REPORT_TRANSPORT_INDEX + i as u32, *value);
+        }
+    }
+}
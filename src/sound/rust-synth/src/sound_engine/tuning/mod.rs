@@ -0,0 +1,2 @@
+pub mod scala;
+pub mod tuning_table;
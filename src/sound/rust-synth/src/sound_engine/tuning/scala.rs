@@ -0,0 +1,151 @@
+/// Gamme au format Scala (.scl) : une liste d'intervalles en cents depuis la tonique,
+/// le dernier intervalle étant la période de répétition (en général l'octave).
+#[derive(Debug, Clone)]
+pub struct ScalaScale {
+    pub cents: Vec<f64>,
+}
+
+impl ScalaScale {
+    pub fn equal_temperament() -> Self {
+        Self {
+            cents: (1..=12).map(|i| i as f64 * 100.0).collect(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let mut lines = ScalaScale::content_lines(text);
+
+        // La première ligne est une description libre, sans effet sur l'accordage
+        lines.next().ok_or("Fichier .scl vide")?;
+        let count: usize = lines
+            .next()
+            .and_then(|l| l.split_whitespace().next())
+            .and_then(|l| l.parse().ok())
+            .ok_or("Nombre de notes .scl invalide")?;
+
+        let mut cents = Vec::with_capacity(count);
+        for line in lines.take(count) {
+            let token = line.split_whitespace().next().ok_or("Intervalle vide")?;
+            cents.push(ScalaScale::parse_pitch(token)?);
+        }
+
+        if cents.len() != count {
+            return Err("Le fichier .scl contient moins d'intervalles qu'annoncé");
+        }
+
+        Ok(Self { cents })
+    }
+
+    /// Les lignes commençant par '!' sont des commentaires
+    fn content_lines(text: &str) -> impl Iterator<Item = &str> {
+        text.lines().filter(|l| !l.trim_start().starts_with('!'))
+    }
+
+    /// Un intervalle contenant un point est en cents, sinon c'est un ratio (3/2 ou 2)
+    fn parse_pitch(token: &str) -> Result<f64, &'static str> {
+        if token.contains('.') {
+            return token.parse().map_err(|_| "Intervalle en cents invalide");
+        }
+
+        let (numerator, denominator) = match token.split_once('/') {
+            Some((n, d)) => (n.parse::<f64>(), d.parse::<f64>()),
+            None => (token.parse::<f64>(), Ok(1.0)),
+        };
+
+        match (numerator, denominator) {
+            (Ok(n), Ok(d)) if n > 0.0 && d > 0.0 => Ok(1200.0 * (n / d).log2()),
+            _ => Err("Ratio invalide"),
+        }
+    }
+
+    pub fn period(&self) -> f64 {
+        self.cents.last().copied().unwrap_or(1200.0)
+    }
+
+    /// Hauteur en cents d'un degré quelconque, négatif ou au-delà de la période
+    pub fn degree_cents(&self, degree: i32) -> f64 {
+        let size = self.cents.len() as i32;
+        if size == 0 {
+            return degree as f64 * 100.0;
+        }
+
+        let period_count = degree.div_euclid(size);
+        let index = degree.rem_euclid(size) as usize;
+        let in_period = if index == 0 {
+            0.0
+        } else {
+            self.cents[index - 1]
+        };
+
+        period_count as f64 * self.period() + in_period
+    }
+}
+
+/// Correspondance clavier au format Scala (.kbm)
+#[derive(Debug, Clone)]
+pub struct KeyboardMapping {
+    pub first_note: u8,
+    pub last_note: u8,
+    pub middle_note: u8,
+    pub reference_note: u8,
+    pub reference_frequency: f64,
+    pub octave_degree: i32,
+    /// Degré joué par chaque touche du motif, `None` pour une touche muette.
+    /// Vide = correspondance linéaire (une touche par degré).
+    pub map: Vec<Option<i32>>,
+}
+
+impl KeyboardMapping {
+    pub fn standard() -> Self {
+        Self {
+            first_note: 0,
+            last_note: 127,
+            middle_note: 60,
+            reference_note: 69,
+            reference_frequency: 440.0,
+            octave_degree: 0,
+            map: Vec::new(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let mut lines = text
+            .lines()
+            .filter(|l| !l.trim_start().starts_with('!') && !l.trim().is_empty())
+            .map(|l| l.split_whitespace().next().unwrap_or(""));
+
+        let mut next_number = |error: &'static str| -> Result<f64, &'static str> {
+            lines
+                .next()
+                .and_then(|l| l.parse::<f64>().ok())
+                .ok_or(error)
+        };
+
+        let size = next_number("Taille du motif .kbm invalide")? as usize;
+        let first_note = next_number("Première note .kbm invalide")? as u8;
+        let last_note = next_number("Dernière note .kbm invalide")? as u8;
+        let middle_note = next_number("Note centrale .kbm invalide")? as u8;
+        let reference_note = next_number("Note de référence .kbm invalide")? as u8;
+        let reference_frequency = next_number("Fréquence de référence .kbm invalide")?;
+        let octave_degree = next_number("Degré d'octave .kbm invalide")? as i32;
+
+        // Les entrées manquantes en fin de motif sont muettes
+        let map = (0..size)
+            .map(|_| lines.next().and_then(|l| l.parse::<i32>().ok()))
+            .collect();
+
+        if reference_frequency <= 0.0 {
+            return Err("Fréquence de référence .kbm invalide");
+        }
+
+        Ok(Self {
+            first_note: first_note.min(127),
+            last_note: last_note.min(127),
+            middle_note: middle_note.min(127),
+            reference_note: reference_note.min(127),
+            reference_frequency,
+            octave_degree,
+            map,
+        })
+    }
+}
@@ -0,0 +1,171 @@
+use web_sys::console;
+
+use crate::{
+    sound_engine::tuning::scala::{KeyboardMapping, ScalaScale},
+    utils::constants::FREQ_A4,
+};
+
+/// Table des fréquences des 128 notes MIDI, calculée à partir d'une gamme Scala,
+/// d'une correspondance clavier et de surcharges par note (à la manière du MIDI Tuning Standard).
+pub struct TuningTable {
+    scale: ScalaScale,
+    mapping: KeyboardMapping,
+    overrides: [Option<f32>; 128],
+    frequencies: [f32; 128],
+    mapped: [bool; 128],
+    /// Accord fin (cents) et grossier (demi-tons) appliqués à toutes les notes, réglés
+    /// depuis l'interface ; les RPN 1 et 2 accordent chaque canal séparément
+    fine_tuning: f32,
+    coarse_tuning: f32,
+    tuning_ratio: f32,
+}
+
+impl TuningTable {
+    pub fn new() -> Self {
+        let mut table = Self {
+            scale: ScalaScale::equal_temperament(),
+            mapping: KeyboardMapping::standard(),
+            overrides: [None; 128],
+            frequencies: [0.0; 128],
+            mapped: [true; 128],
+            fine_tuning: 0.0,
+            coarse_tuning: 0.0,
+            tuning_ratio: 1.0,
+        };
+        table.rebuild();
+        table
+    }
+
+    pub fn frequency(&self, note: u8) -> f32 {
+        let note = note.min(127) as usize;
+        self.overrides[note].unwrap_or(self.frequencies[note]) * self.tuning_ratio
+    }
+
+    /// Une touche hors de la plage du .kbm ou marquée 'x' ne doit rien jouer
+    pub fn is_mapped(&self, note: u8) -> bool {
+        self.mapped[note.min(127) as usize]
+    }
+
+    pub fn set_scale(&mut self, scale: ScalaScale) {
+        self.scale = scale;
+        self.rebuild();
+    }
+
+    pub fn set_mapping(&mut self, mapping: KeyboardMapping) {
+        self.mapping = mapping;
+        self.rebuild();
+    }
+
+    pub fn set_reference_frequency(&mut self, frequency: f32) {
+        if frequency > 0.0 {
+            self.mapping.reference_frequency = frequency as f64;
+            self.rebuild();
+        }
+    }
+
+    pub fn set_reference_note(&mut self, note: u8) {
+        self.mapping.reference_note = note.min(127);
+        self.rebuild();
+    }
+
+    /// `pitch` est une hauteur absolue en demi-tons MIDI fractionnaires (69.0 = A4 à 440 Hz),
+    /// une valeur négative supprime la surcharge
+    pub fn set_note_override(&mut self, note: u8, pitch: f32) {
+        let note = note.min(127) as usize;
+        self.overrides[note] = if pitch < 0.0 {
+            None
+        } else {
+            Some(FREQ_A4 * 2.0f32.powf((pitch - 69.0) / 12.0))
+        };
+    }
+
+    pub fn set_fine_tuning(&mut self, cents: f32) {
+        self.fine_tuning = cents.clamp(-100.0, 100.0);
+        self.update_tuning_ratio();
+    }
+
+    pub fn set_coarse_tuning(&mut self, semitones: f32) {
+        self.coarse_tuning = semitones.clamp(-64.0, 63.0);
+        self.update_tuning_ratio();
+    }
+
+    fn update_tuning_ratio(&mut self) {
+        let cents = self.coarse_tuning * 100.0 + self.fine_tuning;
+        self.tuning_ratio = 2.0f32.powf(cents / 1200.0);
+    }
+
+    pub fn clear_overrides(&mut self) {
+        self.overrides = [None; 128];
+    }
+
+    pub fn reset(&mut self) {
+        self.scale = ScalaScale::equal_temperament();
+        self.mapping = KeyboardMapping::standard();
+        self.clear_overrides();
+        self.fine_tuning = 0.0;
+        self.coarse_tuning = 0.0;
+        self.update_tuning_ratio();
+        self.rebuild();
+    }
+
+    pub fn update(&mut self, param: u32, index: u32, value: f32) {
+        match param {
+            0 => self.set_reference_frequency(value),
+            1 => self.set_reference_note(value as u8),
+            2 => self.set_note_override(index as u8, value),
+            3 => self.clear_overrides(),
+            4 => self.reset(),
+            5 => self.set_fine_tuning(value),
+            6 => self.set_coarse_tuning(value),
+            _ => console::error_1(&format!("Cannot update tuning {}", param).into()),
+        }
+    }
+
+    /// Hauteur en cents d'une touche par rapport à la note centrale du .kbm
+    fn note_cents(&self, note: i32) -> Option<f64> {
+        let offset = note - self.mapping.middle_note as i32;
+
+        if self.mapping.map.is_empty() {
+            return Some(self.scale.degree_cents(offset));
+        }
+
+        let size = self.mapping.map.len() as i32;
+        let repetition = offset.div_euclid(size);
+        let degree = self.mapping.map[offset.rem_euclid(size) as usize]?;
+
+        let octave_degree = if self.mapping.octave_degree > 0 {
+            self.mapping.octave_degree
+        } else {
+            self.scale.cents.len() as i32
+        };
+
+        Some(
+            repetition as f64 * self.scale.degree_cents(octave_degree)
+                + self.scale.degree_cents(degree),
+        )
+    }
+
+    fn rebuild(&mut self) {
+        let reference_note = self.mapping.reference_note as i32;
+        let reference_cents = self.note_cents(reference_note).unwrap_or_else(|| {
+            self.scale
+                .degree_cents(reference_note - self.mapping.middle_note as i32)
+        });
+
+        for note in 0..128 {
+            let in_range =
+                note >= self.mapping.first_note as i32 && note <= self.mapping.last_note as i32;
+            let cents = self.note_cents(note);
+
+            self.mapped[note as usize] = in_range && cents.is_some();
+            self.frequencies[note as usize] = match cents {
+                Some(cents) => {
+                    (self.mapping.reference_frequency
+                        * 2f64.powf((cents - reference_cents) / 1200.0)) as f32
+                }
+                // Les touches muettes gardent une fréquence tempérée par défaut
+                None => FREQ_A4 * 2.0f32.powf((note as f32 - 69.0) / 12.0),
+            };
+        }
+    }
+}
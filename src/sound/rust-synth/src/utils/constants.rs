@@ -14,9 +14,49 @@ pub const FX_EVENT_SIZE_FLOAT: u32 = 1;
 pub const FX_WRITE_INDEX: u32 = 0;
 pub const FX_READ_INDEX: u32 = 1;
 
+pub const CONTROL_QUEUE_CAPACITY: u32 = 64;
+pub const CONTROL_EVENT_SIZE_INT: u32 = 3;
+pub const CONTROL_EVENT_SIZE_FLOAT: u32 = 1;
+
+/// Impulsions d'horloge MIDI par noire, et nombre d'impulsions pour la mesure du tempo
+pub const MIDI_CLOCK_PPQ: u32 = 24;
+pub const MIDI_CLOCK_WINDOW: usize = 48;
+
+/// Emplacements de la réponse dans l'en-tête du canal de fichiers
+pub const FILE_RESPONSE_INDEX: u32 = 3;
+pub const FILE_RESPONSE_KIND: u32 = 4;
+pub const FILE_RESPONSE_LENGTH: u32 = 5;
+/// Le buffer de données est coupé en deux : fichiers reçus, puis réponses du moteur
+pub const FILE_DATA_SIZE: u32 = 1_000_000;
+
 pub const SAMPLE_RATE: f32 = 44100.0;
 pub const FREQ_A4: f32 = 440.0;
 
 pub const OSC_QUEUE_CAPACITY: u32 = 100;
+pub const MAX_UNISON_VOICES: u8 = 16;
+pub const MAX_GRAINS: usize = 32;
+pub const STRETCH_GRAIN_SIZE: u32 = 2048;
+pub const STRETCH_SEARCH_TOLERANCE: u32 = 512;
+
+pub const CHOKE_FADE_MS: f32 = 5.0;
+
+pub const MAX_PARTS: usize = 16;
+pub const MAX_CC_MAPPINGS: usize = 128;
+/// Amplitude du pitch bend hors MPE, en demi-tons
+pub const PITCH_BEND_RANGE: f32 = 2.0;
+
+pub const MAX_PATTERNS: usize = 16;
+pub const MAX_STEPS: usize = 64;
+
+pub const TRANSIENT_FRAME_SIZE: usize = 512;
+pub const TRANSIENT_MIN_SPACING_MS: f32 = 50.0;
+
+/// Disposition du buffer de rapport (Float32) lu par l'interface
+pub const REPORT_TRANSPORT_INDEX: u32 = 0;
+pub const REPORT_CC_LEARN_INDEX: u32 = 8;
+/// Nombre d'effets mesurés puis paires (id, réduction de gain en dB)
+pub const REPORT_METERS_INDEX: u32 = 16;
+pub const MAX_REPORTED_METERS: u32 = 8;
 
 pub const PROCESSING_BUFFER_SIZE: usize = 1024;
+pub const MASTER_GAIN: f32 = 0.1;
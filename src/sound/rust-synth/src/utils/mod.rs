@@ -1,3 +1,4 @@
 pub mod constants;
+pub mod random;
 pub mod toolkit;
 pub mod types;
@@ -0,0 +1,32 @@
+/// Générateur pseudo-aléatoire xorshift32, suffisant pour l'audio et sans allocation
+#[derive(Debug, Clone, Copy)]
+pub struct Random {
+    state: u32,
+}
+
+impl Random {
+    pub const fn new(seed: u32) -> Self {
+        Self {
+            state: if seed == 0 { 0x9E37_79B9 } else { seed },
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// Valeur dans [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Valeur dans [-1, 1)
+    pub fn next_bipolar(&mut self) -> f32 {
+        self.next_f32() * 2.0 - 1.0
+    }
+}
@@ -1,9 +1,18 @@
-use crate::utils::constants::{FREQ_A4, SAMPLE_RATE};
+use crate::{
+    global::TUNING,
+    utils::constants::{FREQ_A4, SAMPLE_RATE},
+};
 
 pub struct ToolKit;
 
 impl ToolKit {
+    /// Fréquence d'une note selon l'accordage courant (gamme Scala, référence, surcharges)
     pub fn midi_to_freq(note: u8) -> f32 {
+        TUNING.with(|t| t.lock().unwrap().frequency(note))
+    }
+
+    /// Fréquence tempérée à A4 = 440 Hz, indépendante de l'accordage
+    pub fn standard_midi_to_freq(note: u8) -> f32 {
         FREQ_A4 * 2.0f32.powf((note as f32 - 69.0) / 12.0)
     }
 
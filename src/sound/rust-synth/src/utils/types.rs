@@ -1,10 +1,21 @@
 use wasm_bindgen::prelude::*;
 
+use crate::utils::toolkit::ToolKit;
+
 #[wasm_bindgen]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum EventType {
     NoteOff = 0,
     NoteOn = 1,
+    ControlChange = 2,
+    /// `data1` porte les 7 bits de poids faible, `data2` ceux de poids fort
+    PitchBend = 3,
+    ChannelPressure = 4,
+    SongPosition = 0xF2,
+    Clock = 0xF8,
+    TransportStart = 0xFA,
+    TransportContinue = 0xFB,
+    TransportStop = 0xFC,
 }
 
 impl TryFrom<u8> for EventType {
@@ -14,6 +25,14 @@ impl TryFrom<u8> for EventType {
         match value {
             0 => Ok(EventType::NoteOff),
             1 => Ok(EventType::NoteOn),
+            2 => Ok(EventType::ControlChange),
+            3 => Ok(EventType::PitchBend),
+            4 => Ok(EventType::ChannelPressure),
+            0xF2 => Ok(EventType::SongPosition),
+            0xF8 => Ok(EventType::Clock),
+            0xFA => Ok(EventType::TransportStart),
+            0xFB => Ok(EventType::TransportContinue),
+            0xFC => Ok(EventType::TransportStop),
             _ => Err("Valeur d'événement MIDI inconnue"),
         }
     }
@@ -24,6 +43,16 @@ impl TryFrom<u8> for EventType {
 pub struct NoteDTO {
     pub value: u8,
     pub velocity: u8,
+    /// Canal MIDI (0-15), sert au routage vers les parties
+    pub channel: u8,
+}
+
+/// Événement brut de la file MIDI, le type est décodé par l'`EventHandler`.
+/// Pour un CC, `note.value` porte le numéro de contrôleur et `note.velocity` sa valeur.
+#[derive(Debug, Clone, Copy)]
+pub struct MidiEventDTO {
+    pub event_type: u8,
+    pub note: NoteDTO,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -48,8 +77,134 @@ pub struct SampleEvent {
     pub hq: u8,
 }
 
+/// Événement de la file de contrôle : un paramètre d'un module global du moteur
+pub struct ControlEventDto {
+    pub target: u32,
+    pub param: u32,
+    pub index: u32,
+    pub value: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ControlTarget {
+    Tuning = 0,
+    Samples = 1,
+    DrumKit = 2,
+    Arpeggiator = 3,
+    Sequencer = 4,
+    Transport = 5,
+    MidiClock = 6,
+    MidiPlayer = 7,
+    Recorder = 8,
+    Part = 9,
+    Mpe = 10,
+    CcMap = 11,
+}
+
+impl TryFrom<u32> for ControlTarget {
+    type Error = &'static str;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ControlTarget::Tuning),
+            1 => Ok(ControlTarget::Samples),
+            2 => Ok(ControlTarget::DrumKit),
+            3 => Ok(ControlTarget::Arpeggiator),
+            4 => Ok(ControlTarget::Sequencer),
+            5 => Ok(ControlTarget::Transport),
+            6 => Ok(ControlTarget::MidiClock),
+            7 => Ok(ControlTarget::MidiPlayer),
+            8 => Ok(ControlTarget::Recorder),
+            9 => Ok(ControlTarget::Part),
+            10 => Ok(ControlTarget::Mpe),
+            11 => Ok(ControlTarget::CcMap),
+            _ => Err("Cible de contrôle inconnue"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileKind {
+    ScalaScale = 0,
+    KeyboardMapping = 1,
+    MidiFile = 2,
+    CcMappings = 3,
+    /// Réponse seulement : courbe d'un égaliseur
+    EqCurve = 4,
+}
+
+impl TryFrom<u32> for FileKind {
+    type Error = &'static str;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(FileKind::ScalaScale),
+            1 => Ok(FileKind::KeyboardMapping),
+            2 => Ok(FileKind::MidiFile),
+            3 => Ok(FileKind::CcMappings),
+            4 => Ok(FileKind::EqCurve),
+            _ => Err("Type de fichier inconnu"),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct FileEvent {
+    pub file_event_index: u32,
+    pub kind: u32,
+    pub length: u32,
+}
+
 pub struct Sample {
     pub id: u32,
     pub values: Box<[f32]>,
+    /// Deux canaux : gauche puis droite, mis bout à bout dans `values`
+    pub channels: u8,
     pub hq: u8,
+    /// Débuts des tranches, triés, en échantillons
+    pub slices: Vec<u32>,
+}
+
+impl Sample {
+    /// Fréquence jouée quand le sample est lu à sa vitesse d'origine
+    pub fn base_frequency(&self) -> f32 {
+        if self.hq == 0 {
+            ToolKit::standard_midi_to_freq(60) // C4
+        } else {
+            ToolKit::standard_midi_to_freq(12) // C0
+        }
+    }
+
+    /// Longueur d'un canal, la droite suivant la gauche dans `values`
+    pub fn channel_length(&self) -> usize {
+        self.values.len() / self.channels.max(1) as usize
+    }
+
+    /// Début et fin d'une tranche. Sans marqueur, le sample entier forme la tranche 0.
+    pub fn slice_bounds(&self, slice_index: usize) -> Option<(u32, u32)> {
+        let length = self.channel_length() as u32;
+
+        if self.slices.is_empty() {
+            return (slice_index == 0).then_some((0, length));
+        }
+
+        let start = *self.slices.get(slice_index)?;
+        let end = self.slices.get(slice_index + 1).copied().unwrap_or(length);
+        Some((start, end))
+    }
+
+    /// Lecture interpolée linéairement, la position boucle sur la longueur du sample
+    pub fn read_interpolated(&self, position: f32) -> f32 {
+        let table = &self.values;
+        if table.is_empty() {
+            return 0.0;
+        }
+
+        let pos_in_table = position.rem_euclid(table.len() as f32);
+        let i0 = (pos_in_table.floor() as usize).min(table.len() - 1);
+        let i1 = (i0 + 1) % table.len();
+
+        let frac = pos_in_table - i0 as f32;
+        (table[i0] * (1.0 - frac)) + (table[i1] * frac)
+    }
 }
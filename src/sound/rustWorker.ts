@@ -26,6 +26,11 @@ self.onmessage = (e: MessageEvent) => {
       fx_queue_buffer,
       sample_event_buffer,
       sample_buffer,
+      control_queue_buffer,
+      file_event_buffer,
+      file_buffer,
+      report_buffer,
+      midi_out_buffer,
     } = e.data;
 
     const buffers = [
@@ -35,6 +40,11 @@ self.onmessage = (e: MessageEvent) => {
       fx_queue_buffer,
       sample_event_buffer,
       sample_buffer,
+      control_queue_buffer,
+      file_event_buffer,
+      file_buffer,
+      report_buffer,
+      midi_out_buffer,
     ];
 
     const all_valid =
@@ -55,7 +65,17 @@ self.onmessage = (e: MessageEvent) => {
         "sample_event_buffer",
         sample_event_buffer instanceof SharedArrayBuffer,
         "sample_buffer: ",
-        sample_buffer instanceof SharedArrayBuffer
+        sample_buffer instanceof SharedArrayBuffer,
+        "control_queue_buffer: ",
+        control_queue_buffer instanceof SharedArrayBuffer,
+        "file_event_buffer: ",
+        file_event_buffer instanceof SharedArrayBuffer,
+        "file_buffer: ",
+        file_buffer instanceof SharedArrayBuffer,
+        "report_buffer: ",
+        report_buffer instanceof SharedArrayBuffer,
+        "midi_out_buffer: ",
+        midi_out_buffer instanceof SharedArrayBuffer
       );
 
       return;
@@ -71,7 +91,12 @@ self.onmessage = (e: MessageEvent) => {
       osc_queue_buffer,
       fx_queue_buffer,
       sample_event_buffer,
-      sample_buffer
+      sample_buffer,
+      control_queue_buffer,
+      file_event_buffer,
+      file_buffer,
+      report_buffer,
+      midi_out_buffer
     );
 
     console.log("[RUST WORKER] initialisation done, processing loop...");
@@ -21,20 +21,108 @@ export enum OscKey {
   PHASE,
   SAMPLE_ID,
   PAN,
+  MODULATION_MODE,
+  MODULATION_TARGET,
+  MODULATION_INDEX,
+  UNISON_VOICES,
+  UNISON_DETUNE,
+  UNISON_DETUNE_CURVE,
+  UNISON_SPREAD,
+  UNISON_PHASE_RANDOMNESS,
+  PLAYBACK_MODE,
+  GRAIN_SIZE,
+  GRAIN_DENSITY,
+  GRAIN_POSITION,
+  GRAIN_POSITION_JITTER,
+  GRAIN_PITCH_JITTER,
+  GRAIN_WINDOW,
+  GRAIN_STEREO_SCATTER,
+  STRETCH_RATE,
+  STRETCH_SOURCE_BPM,
+  STRETCH_TARGET_BPM,
+  SLICE_ROOT_KEY,
+  PRESSURE_TARGET,
+  PRESSURE_AMOUNT,
+  TIMBRE_TARGET,
+  TIMBRE_AMOUNT,
+}
+
+// Destination de la pression et du timbre (CC74) des notes, par sampler
+export enum ExpressionTarget {
+  NONE,
+  GAIN,
+  PITCH,
+  MODULATION_INDEX,
+}
+
+export enum PlaybackMode {
+  PITCHED,
+  GRANULAR,
+  TIME_STRETCH,
+  SLICED,
+}
+
+export enum GrainWindow {
+  HANN,
+  TRIANGLE,
+  TRAPEZOID,
+  GAUSSIAN,
+}
+
+export enum ModulationMode {
+  NONE,
+  PHASE,
+  FREQUENCY,
+  RING,
 }
 
 const FX_EVENT_SIZE = 16;
 const FX_QUEUE_CAPACITY = 64;
 const FX_BUFFER_SIZE = FX_EVENT_SIZE * FX_QUEUE_CAPACITY;
 
+const CONTROL_EVENT_SIZE = 16;
+const CONTROL_QUEUE_CAPACITY = 64;
+const CONTROL_BUFFER_SIZE = CONTROL_EVENT_SIZE * CONTROL_QUEUE_CAPACITY;
+
+// Verrous du séquenceur : paramètre = SEQUENCER_LOCK_OFFSET + clé du sampler
+const SEQUENCER_LOCK_OFFSET = 100;
+const MAX_STEPS = 64;
+
+// Rapport du moteur (Float32), à relire côté interface
+const REPORT_BUFFER_LENGTH = 64;
+const REPORT_TRANSPORT_INDEX = 0;
+const REPORT_CC_LEARN_INDEX = 8;
+const REPORT_METERS_INDEX = 16;
+
+// index, type et taille du fichier envoyé, puis index, type et taille de la réponse du moteur
+const FILE_EVENT_SIZE = 6 * Int32Array.BYTES_PER_ELEMENT;
+const FILE_RESPONSE_INDEX = 3;
+const FILE_RESPONSE_KIND = 4;
+const FILE_RESPONSE_LENGTH = 5;
+// Le buffer de fichiers contient deux zones de cette taille : envois, puis réponses du moteur
+const MAX_FILE_SIZE = 1_000_000;
+
 const MAX_SAMPLE_LENGTH = 2 * 8_000_000;
 const SAMPLE_EVENT_SIZE = 6 * Int32Array.BYTES_PER_ELEMENT;
+// sampler_id qui ne correspond à aucun sampler : le sample est seulement stocké
+const NO_SAMPLER_ID = 0xff;
 
 export type EffectParams = { index: number; value: number };
 
 export enum Effects {
   ECHO,
   FILTER,
+  REVERB,
+  CONVOLUTION,
+  CHORUS,
+  FLANGER,
+  PHASER,
+  DISTORTION,
+  BITCRUSHER,
+  COMPRESSOR,
+  LIMITER,
+  GATE,
+  EQUALIZER,
 }
 
 export enum EchoParams {
@@ -44,6 +132,7 @@ export enum EchoParams {
   L_DELAY_OFFSET,
   DRY,
   WET,
+  SYNC,
 }
 
 export enum FilterParams {
@@ -53,6 +142,384 @@ export enum FilterParams {
   GAIN,
 }
 
+// Valeurs de FilterParams.TYPE, les variantes 24 et 48 dB/oct cascadent des cellules
+export enum FilterType {
+  LOWPASS,
+  HIGHPASS,
+  BELL,
+  BANDPASS,
+  NOTCH,
+  ALLPASS,
+  LOW_SHELF,
+  HIGH_SHELF,
+  LOWPASS_24,
+  HIGHPASS_24,
+  LOWPASS_48,
+  HIGHPASS_48,
+}
+
+// size, damping, diffusion, modulation : entre 0 et 1, decay : en secondes, pre_delay : en ms
+export enum ReverbParams {
+  SIZE,
+  DECAY,
+  PRE_DELAY,
+  DAMPING,
+  DIFFUSION,
+  MODULATION,
+  DRY,
+  WET,
+}
+
+// impulse : id d'un sample chargé (négatif pour retirer la réponse)
+export enum ConvolutionParams {
+  IMPULSE,
+  DRY,
+  WET,
+}
+
+// rate : en Hz, depth et delay : en ms, spread : entre 0 et 1
+export enum ChorusParams {
+  RATE,
+  DEPTH,
+  DELAY,
+  VOICES,
+  SPREAD,
+  DRY,
+  WET,
+}
+
+// rate : en Hz, depth et delay : en ms, feedback : entre -0.95 et 0.95
+export enum FlangerParams {
+  RATE,
+  DEPTH,
+  DELAY,
+  FEEDBACK,
+  DRY,
+  WET,
+}
+
+// depth : entre 0 et 1 (deux octaves), frequency : en Hz, stages : pair, jusqu'à 12
+export enum PhaserParams {
+  RATE,
+  DEPTH,
+  FREQUENCY,
+  STAGES,
+  FEEDBACK,
+  DRY,
+  WET,
+}
+
+// drive : en dB, bias : entre -1 et 1, tone : en Hz, oversampling : 1, 2, 4 ou 8
+export enum DistortionParams {
+  CURVE,
+  DRIVE,
+  BIAS,
+  TONE,
+  OVERSAMPLING,
+  DRY,
+  WET,
+}
+
+export enum DistortionCurve {
+  SOFT_CLIP,
+  HARD_CLIP,
+  FOLDBACK,
+  TUBE,
+  TABLE,
+}
+
+// bits : entre 1 et 24, rate : en Hz, dither et jitter : entre 0 et 1
+export enum BitcrusherParams {
+  BITS,
+  RATE,
+  DITHER,
+  JITTER,
+  DRY,
+  WET,
+}
+
+// threshold, knee, makeup : en dB, attack, release : en ms, link : entre 0 et 1
+export enum CompressorParams {
+  THRESHOLD,
+  RATIO,
+  KNEE,
+  ATTACK,
+  RELEASE,
+  MAKEUP,
+  LINK,
+}
+
+// ceiling, input_gain : en dB, release, lookahead : en ms (anticipation jusqu'à 10 ms)
+export enum LimiterParams {
+  CEILING,
+  INPUT_GAIN,
+  RELEASE,
+  LOOKAHEAD,
+}
+
+// threshold, range (négatif), hysteresis : en dB, attack, hold, release : en ms
+export enum GateParams {
+  THRESHOLD,
+  RANGE,
+  ATTACK,
+  HOLD,
+  RELEASE,
+  HYSTERESIS,
+}
+
+// Champs d'une bande d'égaliseur : type (FilterType), fréquence en Hz, gain en dB, Q, activation
+export enum EqBandParams {
+  TYPE,
+  FREQUENCY,
+  GAIN,
+  Q,
+  ENABLED,
+}
+
+export interface EqCurvePoint {
+  frequency: number;
+  gain_db: number;
+}
+
+export const MAX_EQ_BANDS = 8;
+const EQ_BAND_PARAMS = 8;
+// Paramètre qui demande la courbe de réponse, renvoyée comme un fichier
+const EQ_CURVE_PARAM = MAX_EQ_BANDS * EQ_BAND_PARAMS;
+
+// Index du premier point de la courbe personnalisée
+const DISTORTION_TABLE_PARAM = 16;
+const DISTORTION_TABLE_SIZE = 17;
+
+export type FxParams =
+  | EchoParams
+  | FilterParams
+  | ReverbParams
+  | ConvolutionParams
+  | ChorusParams
+  | FlangerParams
+  | PhaserParams
+  | DistortionParams
+  | BitcrusherParams
+  | CompressorParams
+  | LimiterParams
+  | GateParams;
+
+export enum ControlTarget {
+  TUNING,
+  SAMPLES,
+  DRUM_KIT,
+  ARPEGGIATOR,
+  SEQUENCER,
+  TRANSPORT,
+  MIDI_CLOCK,
+  MIDI_PLAYER,
+  RECORDER,
+  PART,
+  MPE,
+  CC_MAP,
+}
+
+export enum CcMapParams {
+  TARGET,
+  TARGET_ID,
+  TARGET_PARAM,
+  CHANNEL,
+  CONTROLLER_KIND,
+  NUMBER,
+  MIN,
+  MAX,
+  CURVE,
+  CLEAR,
+  LEARN,
+  CANCEL_LEARN,
+  EXPORT,
+  CLEAR_ALL,
+}
+
+export enum MappingTarget {
+  SAMPLER,
+  FX,
+}
+
+export enum ControllerKind {
+  CONTROL_CHANGE,
+  NRPN,
+}
+
+export enum MappingCurve {
+  LINEAR,
+  EXPONENTIAL,
+  LOGARITHMIC,
+  TOGGLE,
+}
+
+// target_param : clé OscKey pour un sampler, index de paramètre pour un effet.
+// min et max dans les unités de l'interface (ms, demi-tons...), channel absent pour omni
+export interface CcMapping {
+  target: MappingTarget;
+  target_id: number;
+  target_param: number;
+  channel?: number;
+  kind: ControllerKind;
+  number: number;
+  min: number;
+  max: number;
+  curve: MappingCurve;
+}
+
+export interface CcLearnState {
+  learning: number | null;
+  last_learned: { slot: number; channel: number; kind: ControllerKind; number: number } | null;
+}
+
+export enum MpeParams {
+  LOWER_ZONE_MEMBERS,
+  UPPER_ZONE_MEMBERS,
+  LOWER_BEND_RANGE,
+  UPPER_BEND_RANGE,
+  LOWER_MASTER_BEND_RANGE,
+  UPPER_MASTER_BEND_RANGE,
+}
+
+export enum PartParams {
+  ADD,
+  REMOVE,
+  CHANNEL,
+  KEY_LOW,
+  KEY_HIGH,
+  GAIN,
+  ENABLED,
+}
+
+export enum RecorderParams {
+  START,
+  STOP,
+  EXPORT,
+  CLEAR,
+}
+
+export enum MidiPlayerParams {
+  LOOP_ENABLED,
+  LOOP_START,
+  LOOP_END,
+  MUTE_TRACK,
+  FOLLOW_TEMPO,
+  CLEAR,
+}
+
+export enum TransportParams {
+  PLAYING,
+  BPM,
+  NUMERATOR,
+  DENOMINATOR,
+  POSITION,
+}
+
+export interface TransportState {
+  playing: boolean;
+  bpm: number;
+  position: number;
+  bar: number;
+  beat: number;
+  numerator: number;
+  denominator: number;
+}
+
+// Réduction de gain en dB (positive) d'un compresseur, limiteur ou porte
+export interface GainReduction {
+  fx_id: number;
+  reduction: number;
+}
+
+export enum MidiEventType {
+  NOTE_OFF = 0,
+  NOTE_ON = 1,
+  CONTROL_CHANGE = 2,
+  PITCH_BEND = 3,
+  CHANNEL_PRESSURE = 4,
+  SONG_POSITION = 0xf2,
+  CLOCK = 0xf8,
+  TRANSPORT_START = 0xfa,
+  TRANSPORT_CONTINUE = 0xfb,
+  TRANSPORT_STOP = 0xfc,
+}
+
+export enum MidiClockParams {
+  RECEIVE,
+  SEND,
+}
+
+export enum SequencerParams {
+  RATE,
+  SELECT_PATTERN,
+  PATTERN_LENGTH,
+  STEP_ACTIVE,
+  STEP_NOTE,
+  STEP_VELOCITY,
+  STEP_GATE,
+  STEP_PROBABILITY,
+  CLEAR_STEP_LOCKS,
+  CHANNEL,
+}
+
+export enum ArpeggiatorParams {
+  ENABLED,
+  MODE,
+  OCTAVES,
+  RATE,
+  GATE,
+  SWING,
+  LATCH,
+}
+
+export enum ArpMode {
+  UP,
+  DOWN,
+  UP_DOWN,
+  RANDOM,
+  AS_PLAYED,
+}
+
+export enum DrumKitParams {
+  ENABLED,
+  SAMPLE_ID,
+  GAIN,
+  PAN,
+  TUNE,
+  OUTPUT,
+  CHOKE_GROUP,
+  CLEAR_PAD,
+}
+
+export enum PadOutput {
+  MAIN,
+  DIRECT,
+}
+
+export enum SampleParams {
+  ADD_SLICE,
+  CLEAR_SLICES,
+  DETECT_TRANSIENTS,
+}
+
+export enum TuningParams {
+  REFERENCE_FREQUENCY,
+  REFERENCE_NOTE,
+  NOTE_OVERRIDE,
+  CLEAR_OVERRIDES,
+  RESET,
+  FINE_TUNING,
+  COARSE_TUNING,
+}
+
+export enum FileKind {
+  SCALA_SCALE,
+  KEYBOARD_MAPPING,
+  MIDI_FILE,
+  CC_MAPPINGS,
+  EQ_CURVE,
+}
+
 export type SampleEvent = {
   sampler_id: number;
   sample_id: number;
@@ -74,6 +541,9 @@ export class SynthApi {
   private static midi_queue_buffer: SharedArrayBuffer;
   private static midi_queue_array: Uint8Array;
   private static midi_write_index: Int32Array;
+  private static midi_out_buffer: SharedArrayBuffer;
+  private static midi_out_array: Uint8Array;
+  private static midi_out_index: Int32Array;
 
   private static osc_queue_buffer: SharedArrayBuffer;
   private static osc_queue_array: Uint8Array;
@@ -88,6 +558,17 @@ export class SynthApi {
   private static fx_queue_float_array: Float32Array;
   private static fx_write_index: Int32Array;
 
+  private static control_queue_buffer: SharedArrayBuffer;
+  private static control_queue_int_array: Int32Array;
+  private static control_queue_float_array: Float32Array;
+  private static control_write_index: Int32Array;
+
+  private static file_event_buffer: SharedArrayBuffer;
+  private static file_buffer: SharedArrayBuffer;
+  private static report_buffer: SharedArrayBuffer;
+  private static report_array: Float32Array;
+  private static file_event_index = 0;
+
   private nmbr_of_samplers = 0;
   private nmbr_of_fx = 0;
   private static sample_event_index = 0;
@@ -104,8 +585,20 @@ export class SynthApi {
     SynthApi.init_sample_processor_worker();
 
     SynthApi.init_midi_queue();
+    SynthApi.init_midi_out_queue();
     SynthApi.init_osc_queue();
     SynthApi.init_fx_queue();
+    SynthApi.init_control_queue();
+    SynthApi.init_file_channel();
+    SynthApi.init_report_buffer();
+  }
+
+  // File remplie par le moteur (horloge MIDI sortante), même format que la file d'entrée
+  private static init_midi_out_queue() {
+    const control_size = 2 * Int32Array.BYTES_PER_ELEMENT;
+    SynthApi.midi_out_buffer = new SharedArrayBuffer(control_size + MIDI_BUFFER_SIZE);
+    SynthApi.midi_out_index = new Int32Array(SynthApi.midi_out_buffer, 0, 2);
+    SynthApi.midi_out_array = new Uint8Array(SynthApi.midi_out_buffer, control_size);
   }
 
   private static init_midi_queue() {
@@ -143,25 +636,127 @@ export class SynthApi {
     );
   }
 
+  private static init_control_queue() {
+    const control_size = 2 * Int32Array.BYTES_PER_ELEMENT;
+    SynthApi.control_queue_buffer = new SharedArrayBuffer(control_size + CONTROL_BUFFER_SIZE);
+
+    SynthApi.control_write_index = new Int32Array(SynthApi.control_queue_buffer, 0, 2);
+
+    SynthApi.control_queue_int_array = new Int32Array(
+      SynthApi.control_queue_buffer,
+      control_size,
+      3 * CONTROL_QUEUE_CAPACITY
+    );
+
+    SynthApi.control_queue_float_array = new Float32Array(
+      SynthApi.control_queue_buffer,
+      control_size + 12 * CONTROL_QUEUE_CAPACITY,
+      CONTROL_QUEUE_CAPACITY
+    );
+  }
+
+  private static init_file_channel() {
+    SynthApi.file_event_buffer = new SharedArrayBuffer(FILE_EVENT_SIZE);
+    SynthApi.file_buffer = new SharedArrayBuffer(2 * MAX_FILE_SIZE);
+  }
+
+  private static init_report_buffer() {
+    SynthApi.report_buffer = new SharedArrayBuffer(
+      Float32Array.BYTES_PER_ELEMENT * REPORT_BUFFER_LENGTH
+    );
+    SynthApi.report_array = new Float32Array(SynthApi.report_buffer);
+  }
+
   async init() {
     await SynthApi.soundEngine.init(
       SynthApi.midi_queue_buffer,
       SynthApi.osc_queue_buffer,
       SynthApi.fx_queue_buffer,
       SynthApi.sampler_event_buffer,
-      SynthApi.sample_buffer
+      SynthApi.sample_buffer,
+      SynthApi.control_queue_buffer,
+      SynthApi.file_event_buffer,
+      SynthApi.file_buffer,
+      SynthApi.report_buffer,
+      SynthApi.midi_out_buffer
     );
   }
 
   static playNote(note: noteDTO) {
-    SynthApi.writeToMidiQueue(1, note.value, note.velocity ?? 100);
+    SynthApi.writeToMidiQueue(1, note.value, note.velocity ?? 100, note.channel);
+  }
+
+  static stopNote(value: number, channel = 0) {
+    SynthApi.writeToMidiQueue(1, value, 0, channel);
+  }
+
+  // value : entre 0 et 16383, centré sur 8192
+  static sendPitchBend(value: number, channel = 0) {
+    SynthApi.writeToMidiQueue(MidiEventType.PITCH_BEND, value & 0x7f, (value >> 7) & 0x7f, channel);
+  }
+
+  static sendChannelPressure(value: number, channel = 0) {
+    SynthApi.writeToMidiQueue(MidiEventType.CHANNEL_PRESSURE, value, 0, channel);
+  }
+
+  static startTransport() {
+    SynthApi.writeToMidiQueue(MidiEventType.TRANSPORT_START, 0, 0);
+  }
+
+  static stopTransport() {
+    SynthApi.writeToMidiQueue(MidiEventType.TRANSPORT_STOP, 0, 0);
+  }
+
+  // Messages temps réel reçus d'un appareil externe (horloge, start, stop, continue, position)
+  static sendMidiRealtime(status: number, data_1 = 0, data_2 = 0) {
+    SynthApi.writeToMidiQueue(status, data_1, data_2);
+  }
+
+  // Vide la file sortante : chaque message est rendu sous forme d'octets MIDI bruts
+  static drainMidiOut(handler: (message: number[]) => void) {
+    // File pas encore créée tant que le moteur n'a pas démarré
+    if (!SynthApi.midi_out_index) return;
+
+    let read_pos = Atomics.load(SynthApi.midi_out_index, 1);
+    const write_pos = Atomics.load(SynthApi.midi_out_index, 0);
+
+    while (read_pos !== write_pos) {
+      const offset = read_pos * MIDI_EVENT_SIZE;
+      const status = SynthApi.midi_out_array[offset];
+
+      if (status === MidiEventType.SONG_POSITION) {
+        const data_1 = SynthApi.midi_out_array[offset + 1];
+        const data_2 = SynthApi.midi_out_array[offset + 2];
+        handler([status, data_1, data_2]);
+      } else {
+        handler([status]);
+      }
+
+      read_pos = (read_pos + 1) % MIDI_QUEUE_CAPACITY;
+    }
+
+    Atomics.store(SynthApi.midi_out_index, 1, read_pos);
+  }
+
+  static sendControlChange(controller: number, value: number, channel = 0) {
+    SynthApi.writeToMidiQueue(MidiEventType.CONTROL_CHANGE, controller, value, channel);
   }
 
-  static stopNote(value: number) {
-    SynthApi.writeToMidiQueue(1, value, 0);
+  // Séquence NRPN (ou RPN) complète : sélection du paramètre puis valeur 14 bits
+  static sendParameterNumber(number: number, value: number, channel = 0, registered = false) {
+    const [select_msb, select_lsb] = registered ? [101, 100] : [99, 98];
+    SynthApi.sendControlChange(select_msb, (number >> 7) & 0x7f, channel);
+    SynthApi.sendControlChange(select_lsb, number & 0x7f, channel);
+    SynthApi.sendControlChange(6, (value >> 7) & 0x7f, channel);
+    SynthApi.sendControlChange(38, value & 0x7f, channel);
   }
 
-  private static writeToMidiQueue(event_type: number, note: number, velocity: number) {
+  private static writeToMidiQueue(
+    event_type: number,
+    note: number,
+    velocity: number,
+    channel = 0
+  ) {
     const write_pos = Atomics.load(SynthApi.midi_write_index, 0);
     const read_pos = Atomics.load(SynthApi.midi_write_index, 1);
 
@@ -176,17 +771,13 @@ export class SynthApi {
     SynthApi.midi_queue_array[event_offset] = event_type;
     SynthApi.midi_queue_array[event_offset + 1] = note;
     SynthApi.midi_queue_array[event_offset + 2] = velocity;
-    SynthApi.midi_queue_array[event_offset + 3] = 0;
+    SynthApi.midi_queue_array[event_offset + 3] = channel & 0x0f;
 
     Atomics.store(SynthApi.midi_write_index, 0, next_write_pos);
   }
 
-  private static writeToOscQueue(
-    event_type: number,
-    osc_index: number,
-    key: OscKey,
-    value: number
-  ) {
+  // Convertit une valeur de l'interface dans les unités attendues par le moteur
+  private static convert_osc_value(key: OscKey, value: number): number {
     if (key === OscKey.SAMPLE_ID) {
     } else if (key === OscKey.PITCH) {
       value = this.convert_semitone_to_frequency_shift(value);
@@ -194,10 +785,21 @@ export class SynthApi {
       key === OscKey.ATTACK ||
       key === OscKey.DECAY ||
       key === OscKey.RELEASE ||
-      key === OscKey.DELAY
+      key === OscKey.DELAY ||
+      key === OscKey.GRAIN_SIZE
     ) {
       value = this.convert_ms_to_sample(value);
     }
+    return value;
+  }
+
+  private static writeToOscQueue(
+    event_type: number,
+    osc_index: number,
+    key: OscKey,
+    value: number
+  ) {
+    value = SynthApi.convert_osc_value(key, value);
     const writePos = Atomics.load(SynthApi.osc_write_index, 0);
     const readPos = Atomics.load(SynthApi.osc_write_index, 1);
 
@@ -222,10 +824,11 @@ export class SynthApi {
     Atomics.store(SynthApi.osc_write_index, 0, nextWrite);
   }
 
-  public create_sampler() {
+  // part : partie qui reçoit le sampler, la partie 0 existe toujours
+  public create_sampler(part = 0) {
     const id = this.nmbr_of_samplers;
 
-    SynthApi.writeToOscQueue(0, id, 0, 0);
+    SynthApi.writeToOscQueue(0, id, 0, part);
     this.nmbr_of_samplers++;
     return id;
   }
@@ -274,21 +877,386 @@ export class SynthApi {
     Atomics.store(SynthApi.fx_write_index, 0, next_write_pos);
   }
 
-  add_fx(param_index: number) {
+  // part : chaîne d'effets de la partie, le bus maître si absente
+  add_fx(param_index: number, part?: number) {
     const id = Number(JSON.parse(JSON.stringify(this.nmbr_of_fx)));
-    SynthApi.write_to_fx_queue(id, 0, param_index, 0);
+    const bus = part === undefined ? 0 : part + 1;
+    SynthApi.write_to_fx_queue(id, 0, param_index, bus);
     this.nmbr_of_fx++;
     return id;
   }
 
-  edit_fx(id: number, param_index: EchoParams | FilterParams, param_value: number) {
+  edit_fx(id: number, param_index: FxParams, param_value: number) {
     SynthApi.write_to_fx_queue(id, 2, param_index, param_value);
   }
 
+  // points : sorties de la courbe pour des entrées réparties uniformément entre -1 et 1
+  set_distortion_table(id: number, points: number[]) {
+    if (points.length !== DISTORTION_TABLE_SIZE) {
+      console.warn(`La courbe doit contenir ${DISTORTION_TABLE_SIZE} points`);
+      return;
+    }
+    points.forEach((point, i) => {
+      SynthApi.write_to_fx_queue(id, 2, DISTORTION_TABLE_PARAM + i, point);
+    });
+  }
+
+  set_eq_band(id: number, band: number, param: EqBandParams, value: number) {
+    SynthApi.write_to_fx_queue(id, 2, band * EQ_BAND_PARAMS + param, value);
+  }
+
+  // Réponse cumulée des bandes actives, de 20 Hz à 20 kHz en échelle logarithmique
+  async get_eq_curve(id: number): Promise<EqCurvePoint[]> {
+    const response = SynthApi.wait_file_response(FileKind.EQ_CURVE);
+    SynthApi.write_to_fx_queue(id, 2, EQ_CURVE_PARAM, 0);
+
+    const bytes = await response;
+    const values = new Float32Array(bytes.buffer, bytes.byteOffset, bytes.length / 4);
+    const curve: EqCurvePoint[] = [];
+    for (let i = 0; i + 1 < values.length; i += 2) {
+      curve.push({ frequency: values[i], gain_db: values[i + 1] });
+    }
+    return curve;
+  }
+
   remove_fx(id: number) {
     SynthApi.write_to_fx_queue(id, 1, 0, 0);
   }
 
+  private static write_to_control_queue(
+    target: ControlTarget,
+    param: number,
+    index: number,
+    value: number
+  ) {
+    const write_pos = Atomics.load(SynthApi.control_write_index, 0);
+    const read_pos = Atomics.load(SynthApi.control_write_index, 1);
+
+    const next_write_pos = (write_pos + 1) % CONTROL_QUEUE_CAPACITY;
+    if (next_write_pos === read_pos) {
+      console.warn("Queue de contrôle pleine");
+      return;
+    }
+
+    const int_base = write_pos * 3;
+
+    SynthApi.control_queue_int_array[int_base] = target;
+    SynthApi.control_queue_int_array[int_base + 1] = param;
+    SynthApi.control_queue_int_array[int_base + 2] = index;
+    SynthApi.control_queue_float_array[write_pos] = value;
+
+    Atomics.store(SynthApi.control_write_index, 0, next_write_pos);
+  }
+
+  private static send_file(kind: FileKind, bytes: Uint8Array) {
+    if (bytes.length > MAX_FILE_SIZE) {
+      console.warn("Fichier trop volumineux pour être transmis au moteur !");
+      return;
+    }
+
+    new Uint8Array(SynthApi.file_buffer, 0, bytes.length).set(bytes);
+
+    const evt = new Int32Array(SynthApi.file_event_buffer);
+    SynthApi.file_event_index++;
+    evt[1] = kind;
+    evt[2] = bytes.length;
+    evt[0] = SynthApi.file_event_index;
+  }
+
+  // Attend le prochain fichier renvoyé par le moteur et en retourne une copie
+  private static async wait_file_response(kind: FileKind): Promise<Uint8Array> {
+    const evt = new Int32Array(SynthApi.file_event_buffer);
+    const last_index = Atomics.load(evt, FILE_RESPONSE_INDEX);
+
+    while (Atomics.load(evt, FILE_RESPONSE_INDEX) === last_index) {
+      await new Promise((resolve) => setTimeout(resolve, 10));
+    }
+
+    if (evt[FILE_RESPONSE_KIND] !== kind) {
+      throw new Error("Réponse inattendue du moteur");
+    }
+
+    const length = evt[FILE_RESPONSE_LENGTH];
+    return new Uint8Array(SynthApi.file_buffer, MAX_FILE_SIZE, length).slice();
+  }
+
+  public async load_tuning_file(file: File) {
+    const kind = file.name.toLowerCase().endsWith(".kbm")
+      ? FileKind.KEYBOARD_MAPPING
+      : FileKind.SCALA_SCALE;
+
+    SynthApi.send_file(kind, new Uint8Array(await file.arrayBuffer()));
+  }
+
+  public set_reference_pitch(frequency: number) {
+    SynthApi.write_to_control_queue(
+      ControlTarget.TUNING,
+      TuningParams.REFERENCE_FREQUENCY,
+      0,
+      frequency
+    );
+  }
+
+  // pitch : hauteur absolue en demi-tons MIDI fractionnaires, négative pour annuler
+  public set_note_tuning(note: number, pitch: number) {
+    SynthApi.write_to_control_queue(ControlTarget.TUNING, TuningParams.NOTE_OVERRIDE, note, pitch);
+  }
+
+  public reset_tuning() {
+    SynthApi.write_to_control_queue(ControlTarget.TUNING, TuningParams.RESET, 0, 0);
+  }
+
+  // cents : entre -100 et 100
+  public set_fine_tuning(cents: number) {
+    SynthApi.write_to_control_queue(ControlTarget.TUNING, TuningParams.FINE_TUNING, 0, cents);
+  }
+
+  public set_coarse_tuning(semitones: number) {
+    SynthApi.write_to_control_queue(ControlTarget.TUNING, TuningParams.COARSE_TUNING, 0, semitones);
+  }
+
+  // position : début de la tranche en échantillons
+  public add_slice(sample_id: number, position: number) {
+    SynthApi.write_to_control_queue(
+      ControlTarget.SAMPLES,
+      SampleParams.ADD_SLICE,
+      sample_id,
+      position
+    );
+  }
+
+  public clear_slices(sample_id: number) {
+    SynthApi.write_to_control_queue(ControlTarget.SAMPLES, SampleParams.CLEAR_SLICES, sample_id, 0);
+  }
+
+  // sensitivity : entre 0 et 1
+  public detect_transients(sample_id: number, sensitivity: number) {
+    SynthApi.write_to_control_queue(
+      ControlTarget.SAMPLES,
+      SampleParams.DETECT_TRANSIENTS,
+      sample_id,
+      sensitivity
+    );
+  }
+
+  public set_drum_kit_enabled(enabled: boolean, part = 0) {
+    SynthApi.write_to_control_queue(
+      ControlTarget.DRUM_KIT,
+      DrumKitParams.ENABLED,
+      part << 16,
+      +enabled
+    );
+  }
+
+  // pan : entre -1 et 1, tune : en demi-tons, choke_group : 0 pour aucun groupe
+  public set_drum_pad_param(key: number, param: DrumKitParams, value: number, part = 0) {
+    SynthApi.write_to_control_queue(ControlTarget.DRUM_KIT, param, (part << 16) | key, value);
+  }
+
+  public clear_drum_pad(key: number, part = 0) {
+    SynthApi.write_to_control_queue(
+      ControlTarget.DRUM_KIT,
+      DrumKitParams.CLEAR_PAD,
+      (part << 16) | key,
+      0
+    );
+  }
+
+  // Les parties sont numérotées dans l'ordre d'ajout, un retrait décale les suivantes
+  public add_part() {
+    SynthApi.write_to_control_queue(ControlTarget.PART, PartParams.ADD, 0, 0);
+  }
+
+  public remove_part(part: number) {
+    SynthApi.write_to_control_queue(ControlTarget.PART, PartParams.REMOVE, part, 0);
+  }
+
+  // channel : 0-15, ou -1 pour écouter tous les canaux
+  public set_part_param(part: number, param: PartParams, value: number) {
+    SynthApi.write_to_control_queue(ControlTarget.PART, param, part, value);
+  }
+
+  // members : nombre de canaux membres de la zone, 0 la désactive. Plages en demi-tons
+  public set_mpe_param(param: MpeParams, value: number) {
+    SynthApi.write_to_control_queue(ControlTarget.MPE, param, 0, value);
+  }
+
+  // rate : durée d'un pas en temps (0.25 = double croche), gate et swing : entre 0 et 1
+  public set_arpeggiator_param(param: ArpeggiatorParams, value: number) {
+    SynthApi.write_to_control_queue(ControlTarget.ARPEGGIATOR, param, 0, value);
+  }
+
+  public set_sequencer_param(param: SequencerParams, value: number) {
+    SynthApi.write_to_control_queue(ControlTarget.SEQUENCER, param, 0, value);
+  }
+
+  public set_pattern_length(pattern: number, length: number) {
+    SynthApi.write_to_control_queue(
+      ControlTarget.SEQUENCER,
+      SequencerParams.PATTERN_LENGTH,
+      pattern,
+      length
+    );
+  }
+
+  public set_step_param(pattern: number, step: number, param: SequencerParams, value: number) {
+    SynthApi.write_to_control_queue(
+      ControlTarget.SEQUENCER,
+      param,
+      pattern * MAX_STEPS + step,
+      value
+    );
+  }
+
+  // Le verrou remplace la valeur du paramètre du sampler pendant la durée du pas
+  public set_step_lock(
+    pattern: number,
+    step: number,
+    sampler_id: number,
+    key: OscKey,
+    value: number
+  ) {
+    SynthApi.write_to_control_queue(
+      ControlTarget.SEQUENCER,
+      SEQUENCER_LOCK_OFFSET + key,
+      (sampler_id << 16) | (pattern * MAX_STEPS + step),
+      SynthApi.convert_osc_value(key, value)
+    );
+  }
+
+  public set_transport_playing(playing: boolean) {
+    SynthApi.write_to_control_queue(ControlTarget.TRANSPORT, TransportParams.PLAYING, 0, +playing);
+  }
+
+  // position : en noires depuis le début du morceau
+  public set_transport_param(param: TransportParams, value: number) {
+    SynthApi.write_to_control_queue(ControlTarget.TRANSPORT, param, 0, value);
+  }
+
+  public set_midi_clock_param(param: MidiClockParams, enabled: boolean) {
+    SynthApi.write_to_control_queue(ControlTarget.MIDI_CLOCK, param, 0, +enabled);
+  }
+
+  // Fichier .mid de type 0 ou 1, lu au rythme du transport
+  public async load_midi_file(file: File) {
+    SynthApi.send_file(FileKind.MIDI_FILE, new Uint8Array(await file.arrayBuffer()));
+  }
+
+  // loop_start et loop_end en noires, loop_end à 0 pour boucler jusqu'à la fin du morceau
+  public set_midi_player_param(param: MidiPlayerParams, value: number) {
+    SynthApi.write_to_control_queue(ControlTarget.MIDI_PLAYER, param, 0, value);
+  }
+
+  public mute_midi_track(track: number, muted: boolean) {
+    SynthApi.write_to_control_queue(
+      ControlTarget.MIDI_PLAYER,
+      MidiPlayerParams.MUTE_TRACK,
+      track,
+      +muted
+    );
+  }
+
+  public start_recording() {
+    SynthApi.write_to_control_queue(ControlTarget.RECORDER, RecorderParams.START, 0, 0);
+  }
+
+  public stop_recording() {
+    SynthApi.write_to_control_queue(ControlTarget.RECORDER, RecorderParams.STOP, 0, 0);
+  }
+
+  // Fichier MIDI standard (type 0) de la dernière prise
+  public async export_recording(): Promise<Blob> {
+    const response = SynthApi.wait_file_response(FileKind.MIDI_FILE);
+    SynthApi.write_to_control_queue(ControlTarget.RECORDER, RecorderParams.EXPORT, 0, 0);
+
+    return new Blob([await response], { type: "audio/midi" });
+  }
+
+  public set_cc_mapping(slot: number, mapping: CcMapping) {
+    let { min, max } = mapping;
+    if (mapping.target === MappingTarget.SAMPLER) {
+      min = SynthApi.convert_osc_value(mapping.target_param, min);
+      max = SynthApi.convert_osc_value(mapping.target_param, max);
+    }
+
+    const fields: [CcMapParams, number][] = [
+      [CcMapParams.TARGET, mapping.target],
+      [CcMapParams.TARGET_ID, mapping.target_id],
+      [CcMapParams.TARGET_PARAM, mapping.target_param],
+      [CcMapParams.CHANNEL, mapping.channel ?? -1],
+      [CcMapParams.CONTROLLER_KIND, mapping.kind],
+      [CcMapParams.NUMBER, mapping.number],
+      [CcMapParams.MIN, min],
+      [CcMapParams.MAX, max],
+      [CcMapParams.CURVE, mapping.curve],
+    ];
+    for (const [param, value] of fields) {
+      SynthApi.write_to_control_queue(ControlTarget.CC_MAP, param, slot, value);
+    }
+  }
+
+  public clear_cc_mapping(slot: number) {
+    SynthApi.write_to_control_queue(ControlTarget.CC_MAP, CcMapParams.CLEAR, slot, 0);
+  }
+
+  // Le prochain contrôleur reçu est lié à l'emplacement, voir get_cc_learn_state
+  public learn_cc(slot: number) {
+    SynthApi.write_to_control_queue(ControlTarget.CC_MAP, CcMapParams.LEARN, slot, 0);
+  }
+
+  public cancel_cc_learn() {
+    SynthApi.write_to_control_queue(ControlTarget.CC_MAP, CcMapParams.CANCEL_LEARN, 0, 0);
+  }
+
+  public get_cc_learn_state(): CcLearnState {
+    const report = SynthApi.report_array.subarray(REPORT_CC_LEARN_INDEX);
+    return {
+      learning: report[0] < 0 ? null : report[0],
+      last_learned:
+        report[1] < 0
+          ? null
+          : { slot: report[1], channel: report[2], kind: report[3], number: report[4] },
+    };
+  }
+
+  // Table binaire à sauvegarder avec le patch, rechargée par load_cc_mappings
+  public async export_cc_mappings(): Promise<Uint8Array> {
+    const response = SynthApi.wait_file_response(FileKind.CC_MAPPINGS);
+    SynthApi.write_to_control_queue(ControlTarget.CC_MAP, CcMapParams.EXPORT, 0, 0);
+
+    return response;
+  }
+
+  public load_cc_mappings(bytes: Uint8Array) {
+    SynthApi.send_file(FileKind.CC_MAPPINGS, bytes);
+  }
+
+  public clear_cc_mappings() {
+    SynthApi.write_to_control_queue(ControlTarget.CC_MAP, CcMapParams.CLEAR_ALL, 0, 0);
+  }
+
+  public get_gain_reductions(): GainReduction[] {
+    const report = SynthApi.report_array.subarray(REPORT_METERS_INDEX);
+    const meters: GainReduction[] = [];
+    for (let i = 0; i < report[0]; i++) {
+      meters.push({ fx_id: report[1 + i * 2], reduction: report[2 + i * 2] });
+    }
+    return meters;
+  }
+
+  public get_transport_state(): TransportState {
+    const report = SynthApi.report_array.subarray(REPORT_TRANSPORT_INDEX);
+    return {
+      playing: report[0] !== 0,
+      bpm: report[1],
+      position: report[2],
+      bar: report[3],
+      beat: report[4],
+      numerator: report[5],
+      denominator: report[6],
+    };
+  }
+
   private static init_sample_buffer() {
     SynthApi.sample_buffer = new SharedArrayBuffer(
       Float32Array.BYTES_PER_ELEMENT * MAX_SAMPLE_LENGTH
@@ -430,6 +1398,59 @@ export class SynthApi {
     SynthApi.notify_sample_event(event);
   }
 
+  // Charge un fichier wav comme réponse impulsionnelle de la convolution fx_id
+  public async import_impulse_response(
+    files: FileList | null,
+    fx_id: number
+  ): Promise<SampleData[] | void> {
+    if (!files) return;
+    const file = files[0];
+    if (file.type !== "audio/wav") {
+      console.log("invalid format");
+      return;
+    }
+
+    const array_buffer = await file.arrayBuffer();
+    const audio_ctx = new AudioContext();
+    const audio_buffer = await audio_ctx.decodeAudioData(array_buffer);
+
+    const channels: Float32Array[] = [];
+    for (let i = 0; i < Math.min(audio_buffer.numberOfChannels, 2); i++) {
+      channels.push(audio_buffer.getChannelData(i));
+    }
+
+    const total_length = channels.reduce((sum, channel) => sum + channel.length, 0);
+    if (total_length > MAX_SAMPLE_LENGTH) {
+      console.warn("Réponse impulsionnelle trop longue pour le buffer !");
+      return;
+    }
+
+    const buffer_view = new Float32Array(SynthApi.sample_buffer, 0, total_length);
+    buffer_view.set(channels[0], 0);
+    if (channels.length === 2) {
+      buffer_view.set(channels[1], channels[0].length);
+    }
+
+    const new_sample_id = this.get_new_sample_id();
+    SynthApi.notify_sample_event({
+      sampler_id: NO_SAMPLER_ID,
+      sample_id: new_sample_id,
+      length: total_length,
+      channels: channels.length,
+      hq: 0,
+    });
+    this.edit_fx(fx_id, ConvolutionParams.IMPULSE, new_sample_id);
+
+    this.loaded_samples.push({
+      duration_seconds: audio_buffer.duration,
+      high_quality: false,
+      sample_id: new_sample_id,
+      title: file.name,
+    });
+
+    return this.loaded_samples;
+  }
+
   private handleHqSample(
     audio_buffer: AudioBuffer,
     channels: Float32Array[],
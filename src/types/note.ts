@@ -1,4 +1,5 @@
 export type noteDTO = {
   value: number;
   velocity?: number;
+  channel?: number;
 };